@@ -69,7 +69,6 @@ mod tests {
         let mut limit_tracker = LimitTracker::new(&mock_messenger, 100);
 
         limit_tracker.set_value(80);
-        // ANCHOR: here
 
         // ANCHOR: here
         assert_eq!(mock_messenger.sent_messages.borrow().len(), 1);
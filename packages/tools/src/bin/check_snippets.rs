@@ -0,0 +1,56 @@
+//! Check the anchors and `--snip--` comments in the listings the book includes;
+//! see [`rust_book_tools::snippets`].
+
+use std::error::Error;
+use std::path::Path;
+
+use docopt::Docopt;
+use rust_book_tools::snippets::Report;
+use serde::Deserialize;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Args = Docopt::new(USAGE)
+        .and_then(|d| d.deserialize())
+        .unwrap_or_else(|e| e.exit());
+
+    let report = Report::new(
+        Path::new(&args.flag_src_dir),
+        Path::new(&args.flag_listings_dir),
+    )?;
+    for problem in &report.problems {
+        eprintln!("{problem}");
+    }
+    eprintln!(
+        "\nChecked {} includes of anchors and {} listing files: {} problems.",
+        report.includes,
+        report.files,
+        report.problems.len()
+    );
+
+    if report.failed() {
+        std::process::exit(1)
+    }
+    Ok(())
+}
+
+const USAGE: &str = "
+Check that every anchor the book includes from a listing exists exactly once
+with a matching end, that no anchor is left unterminated or ended without a
+start, and that every `// --snip--` comment is indented like the code around
+it. Anchors which nothing includes are reported as warnings.
+
+Usage:
+  check_snippets [options]
+  check_snippets (-h | --help)
+
+Options:
+  --src-dir=<dir>       The book's Markdown. [default: src]
+  --listings-dir=<dir>  The listings it includes. [default: listings]
+  -h --help             Show this screen.
+";
+
+#[derive(Debug, Deserialize)]
+struct Args {
+    flag_src_dir: String,
+    flag_listings_dir: String,
+}
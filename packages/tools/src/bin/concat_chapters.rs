@@ -1,119 +1,101 @@
-use std::collections::BTreeMap;
-use std::env;
-use std::fs::{File, create_dir, read_dir};
+use std::error::Error;
+use std::fs::{self, create_dir};
 use std::io;
-use std::io::{Read, Write};
-use std::path::{Path, PathBuf};
-use std::process::exit;
+use std::path::Path;
 
-use lazy_static::lazy_static;
-use regex::Regex;
+use docopt::Docopt;
+use rust_book_tools::{ConcatOptions, Summary, concat_chapter_files};
+use serde::Deserialize;
 
-static PATTERNS: &[(&str, &str)] = &[
-    (r"ch(\d\d)-\d\d-.*\.md", "chapter$1.md"),
-    (r"appendix-(\d\d).*\.md", "appendix.md"),
-];
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Args = Docopt::new(USAGE)
+        .and_then(|d| d.deserialize())
+        .unwrap_or_else(|e| e.exit());
 
-lazy_static! {
-    static ref MATCHERS: Vec<(Regex, &'static str)> = {
-        PATTERNS
-            .iter()
-            .map(|&(expr, repl)| (Regex::new(expr).unwrap(), repl))
-            .collect()
-    };
-}
-
-fn main() {
-    let args: Vec<String> = env::args().collect();
-
-    if args.len() < 3 {
-        println!("Usage: {} <src-dir> <target-dir>", args[0]);
-        exit(1);
-    }
-
-    let source_dir = ensure_dir_exists(&args[1]).unwrap();
-    let target_dir = ensure_dir_exists(&args[2]).unwrap();
+    let source_dir = ensure_dir_exists(&args.arg_src_dir)?;
+    let target_dir = ensure_dir_exists(&args.arg_target_dir)?;
 
-    let mut matched_files = match_files(source_dir, target_dir);
-    matched_files.sort();
-
-    for (target_path, source_paths) in group_by_target(matched_files) {
-        concat_files(source_paths, target_path).unwrap();
-    }
-}
+    let summary = fs::read_to_string(&args.flag_summary).map_err(|e| {
+        format!("Could not read summary '{}': {e}", args.flag_summary)
+    })?;
+    let summary = Summary::parse(&summary)?;
 
-fn match_files(
-    source_dir: &Path,
-    target_dir: &Path,
-) -> Vec<(PathBuf, PathBuf)> {
-    read_dir(source_dir)
-        .expect("Unable to read source directory")
-        .filter_map(|maybe_entry| maybe_entry.ok())
-        .filter_map(|entry| {
-            let source_filename = entry.file_name();
-            let source_filename =
-                &source_filename.to_string_lossy().into_owned();
-            for &(ref regex, replacement) in MATCHERS.iter() {
-                if regex.is_match(source_filename) {
-                    let target_filename =
-                        regex.replace_all(source_filename, replacement);
-                    let source_path = entry.path();
-                    let mut target_path = PathBuf::from(&target_dir);
-                    target_path.push(target_filename.to_string());
-                    return Some((source_path, target_path));
-                }
-            }
-            None
-        })
-        .collect()
+    let options = concat_options(&args)?;
+    concat_chapter_files(&summary, &options, source_dir, target_dir)
 }
 
-fn group_by_target(
-    matched_files: Vec<(PathBuf, PathBuf)>,
-) -> BTreeMap<PathBuf, Vec<PathBuf>> {
-    let mut grouped: BTreeMap<PathBuf, Vec<PathBuf>> = BTreeMap::new();
-    for (source, target) in matched_files {
-        if let Some(source_paths) = grouped.get_mut(&target) {
-            source_paths.push(source);
-            continue;
-        }
-        let source_paths = vec![source];
-        grouped.insert(target.clone(), source_paths);
-    }
-    grouped
+const USAGE: &str = "
+Concatenate the sections of the book into one file per chapter, following the
+structure of SUMMARY.md.
+
+All the prefix chapters go in one file, as do all the suffix chapters. Each
+numbered chapter gets its own file, as does each part title. The appendix
+chapter goes in one file too, unless its name template uses {letter}, in which
+case each appendix gets its own. Name templates can use {number} for the
+chapter or part number and {letter} for the appendix letter.
+
+The defaults produce the files in nostarch/, without touching the ones there
+which are written by hand.
+
+Usage:
+  concat_chapters [options] <src-dir> <target-dir>
+  concat_chapters (-h | --help)
+
+Options:
+  --summary=<path>             The summary describing the book's structure.
+                               [default: src/SUMMARY.md]
+  --frontmatter-name=<name>    [default: chapter00.md]
+  --part-name=<template>       [default: part{number}.md]
+  --chapter-name=<template>    [default: chapter{number}.md]
+  --appendix-name=<template>   [default: appendix.md]
+  --backmatter-name=<name>     [default: backmatter.md]
+  --appendix-title=<prefix>    Title prefix of the chapter containing the
+                               appendices. [default: Appendix]
+  --skip=<files>               Comma-separated files in the summary to leave
+                               out. [default: title-page.md,foreword.md]
+  --header=<file>              Use the contents of <file> as the header of each
+                               file instead of the \"DO NOT EDIT\" comment.
+  --no-toc                     Do not add a [TOC] marker after the header.
+  -h --help                    Show this screen.
+";
+
+#[derive(Debug, Deserialize)]
+struct Args {
+    arg_src_dir: String,
+    arg_target_dir: String,
+    flag_summary: String,
+    flag_frontmatter_name: String,
+    flag_part_name: String,
+    flag_chapter_name: String,
+    flag_appendix_name: String,
+    flag_backmatter_name: String,
+    flag_appendix_title: String,
+    flag_skip: String,
+    flag_header: Option<String>,
+    flag_no_toc: bool,
 }
 
-fn concat_files(
-    source_paths: Vec<PathBuf>,
-    target_path: PathBuf,
-) -> io::Result<()> {
-    println!("Concatenating into {}:", target_path.to_string_lossy());
-    let mut target = File::create(target_path)?;
-
-    write!(
-        target,
-        "\
-<!-- DO NOT EDIT THIS FILE.
-
-This file is periodically generated from the content in the `/src/`
-directory, so all fixes need to be made in `/src/`.
--->
-
-[TOC]
-"
-    )?;
-
-    for path in source_paths {
-        println!("  {}", path.to_string_lossy());
-        let mut source = File::open(path)?;
-        let mut contents: Vec<u8> = Vec::new();
-        source.read_to_end(&mut contents)?;
-
-        target.write_all(b"\n")?;
-        target.write_all(&contents)?;
-        target.write_all(b"\n")?;
+fn concat_options(args: &Args) -> io::Result<ConcatOptions> {
+    let mut options = ConcatOptions {
+        frontmatter_name: args.flag_frontmatter_name.clone(),
+        part_name: args.flag_part_name.clone(),
+        chapter_name: args.flag_chapter_name.clone(),
+        appendix_name: args.flag_appendix_name.clone(),
+        backmatter_name: args.flag_backmatter_name.clone(),
+        appendix_title: args.flag_appendix_title.clone(),
+        skip: args
+            .flag_skip
+            .split(',')
+            .filter(|file| !file.is_empty())
+            .map(String::from)
+            .collect(),
+        toc: !args.flag_no_toc,
+        ..ConcatOptions::default()
+    };
+    if let Some(header) = &args.flag_header {
+        options.header = fs::read_to_string(header)?;
     }
-    Ok(())
+    Ok(options)
 }
 
 fn ensure_dir_exists(dir_string: &str) -> io::Result<&Path> {
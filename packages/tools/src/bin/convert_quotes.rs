@@ -1,78 +1,93 @@
-use std::io;
-use std::io::Read;
+//! Convert straight quotes in Markdown prose to smart quotes; see
+//! [`rust_book_tools::convert_quotes`].
+//!
+//! With `--check`, nothing is rewritten; instead every straight quote found in
+//! prose is reported, and the process exits with a non-zero status if there
+//! were any, so it can be used in CI.
+
+use std::process::exit;
+use std::{fs, path};
+
+use docopt::Docopt;
+use rust_book_tools::cli::{read_md, write_md};
+use rust_book_tools::{convert_quotes, find_straight_quotes};
+use serde::Deserialize;
 
 fn main() {
-    let mut is_in_code_block = false;
-    let mut is_in_inline_code = false;
-    let mut is_in_html_tag = false;
+    let args: Args = Docopt::new(USAGE)
+        .and_then(|d| d.deserialize())
+        .unwrap_or_else(|e| e.exit());
 
-    let mut buffer = String::new();
-    if let Err(e) = io::stdin().read_to_string(&mut buffer) {
-        panic!("{e}");
-    }
+    if args.arg_file.is_empty() {
+        let buffer = read_md();
 
-    for line in buffer.lines() {
-        if line.is_empty() {
-            is_in_inline_code = false;
+        if args.flag_check {
+            let found = report_straight_quotes("<stdin>", &buffer);
+            exit(if found { 1 } else { 0 });
         }
-        if line.starts_with("```") {
-            is_in_code_block = !is_in_code_block;
-        }
-        if is_in_code_block {
-            is_in_inline_code = false;
-            is_in_html_tag = false;
-            println!("{line}");
-        } else {
-            let modified_line = &mut String::new();
-            let mut previous_char = std::char::REPLACEMENT_CHARACTER;
-            let chars_in_line = line.chars();
 
-            for possible_match in chars_in_line {
-                // Check if inside inline code.
-                if possible_match == '`' {
-                    is_in_inline_code = !is_in_inline_code;
-                }
-                // Check if inside HTML tag.
-                if possible_match == '<' && !is_in_inline_code {
-                    is_in_html_tag = true;
-                }
-                if possible_match == '>' && !is_in_inline_code {
-                    is_in_html_tag = false;
-                }
+        write_md(&convert_quotes(&buffer));
+        return;
+    }
+
+    let mut found = false;
+    for file in &args.arg_file {
+        let path = path::Path::new(file);
+        let contents = fs::read_to_string(path).unwrap_or_else(|e| {
+            eprintln!("Unable to read {}: {e}", path.display());
+            exit(2)
+        });
 
-                // Replace with right/left apostrophe/quote.
-                let char_to_push = if possible_match == '\''
-                    && !is_in_inline_code
-                    && !is_in_html_tag
-                {
-                    if (previous_char != std::char::REPLACEMENT_CHARACTER
-                        && !previous_char.is_whitespace())
-                        || previous_char == '‘'
-                    {
-                        '’'
-                    } else {
-                        '‘'
-                    }
-                } else if possible_match == '"'
-                    && !is_in_inline_code
-                    && !is_in_html_tag
-                {
-                    if (previous_char != std::char::REPLACEMENT_CHARACTER
-                        && !previous_char.is_whitespace())
-                        || previous_char == '“'
-                    {
-                        '”'
-                    } else {
-                        '“'
-                    }
-                } else {
-                    // Leave untouched.
-                    possible_match
-                };
-                modified_line.push(char_to_push);
-                previous_char = char_to_push;
+        if args.flag_check {
+            found |= report_straight_quotes(file, &contents);
+        } else {
+            let converted = convert_quotes(&contents);
+            if converted != contents {
+                fs::write(path, converted).unwrap_or_else(|e| {
+                    eprintln!("Unable to write {}: {e}", path.display());
+                    exit(2)
+                });
             }
-            println!("{modified_line}");
         }
     }
+
+    if found {
+        exit(1);
+    }
+}
+
+const USAGE: &str = "
+Convert straight quotes in Markdown prose to smart quotes.
+
+Reads from stdin and writes to stdout when no files are given; otherwise
+rewrites each file in place.
+
+Usage:
+  convert_quotes [--check] [<file>...]
+  convert_quotes (-h | --help)
+
+Options:
+  --check    Report straight quotes in prose instead of converting them, and
+             exit with a non-zero status if any were found.
+  -h --help  Show this screen.
+";
+
+#[derive(Debug, Deserialize)]
+struct Args {
+    flag_check: bool,
+    arg_file: Vec<String>,
+}
+
+/// Print every straight quote in the prose of `input` as `name:line:col`, and
+/// return whether there were any.
+fn report_straight_quotes(name: &str, input: &str) -> bool {
+    let straight_quotes = find_straight_quotes(input);
+    for quote in &straight_quotes {
+        let (line_num, col, line) = quote.locate(input);
+        eprintln!(
+            "{name}:{line_num}:{col}\tstraight {} (expected {})\t{line}",
+            quote.straight, quote.smart
+        );
+    }
+    !straight_quotes.is_empty()
 }
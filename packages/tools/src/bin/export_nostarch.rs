@@ -0,0 +1,189 @@
+//! Produce the Markdown files we send to No Starch Press for print.
+//!
+//! This renders the book to Markdown with mdBook (using the configuration in
+//! `nostarch/book.toml`), runs every rendered file through the transformations
+//! in [`rust_book_tools::nostarch`], and concatenates the results into one file
+//! per chapter.
+
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use docopt::Docopt;
+use rust_book_tools::nostarch::{Pipeline, Stage};
+use rust_book_tools::{ConcatOptions, Summary, concat_chapter_files};
+use serde::Deserialize;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Args = Docopt::new(USAGE)
+        .and_then(|d| d.deserialize())
+        .unwrap_or_else(|e| e.exit());
+
+    let tmp_dir = Path::new(&args.flag_tmp_dir);
+    let markdown_dir = tmp_dir.join("markdown");
+
+    if let Some(stage) = args.flag_dump_stage
+        && stage > Stage::ALL.len()
+    {
+        return Err(format!(
+            "--dump-stage must be between 0 and {}",
+            Stage::ALL.len()
+        )
+        .into());
+    }
+
+    if !args.flag_skip_build {
+        render_markdown(&args.flag_book_dir, &markdown_dir)?;
+    }
+
+    let mut pipeline = Pipeline::default();
+    pipeline.set_enabled(Stage::RemoveLinks, !args.flag_no_remove_links);
+    pipeline.set_enabled(Stage::Link2Print, !args.flag_no_link2print);
+    pipeline.set_enabled(Stage::RemoveMarkup, !args.flag_no_remove_markup);
+    pipeline.set_enabled(
+        Stage::RemoveHiddenLines,
+        !args.flag_no_remove_hidden_lines,
+    );
+    pipeline.set_enabled(
+        Stage::CleanupBlockquotes,
+        !args.flag_no_cleanup_blockquotes,
+    );
+
+    // When dumping a stage, put the files somewhere they will not be picked up
+    // by the concatenation, and stop there.
+    let (processed_dir, last_stage) = match args.flag_dump_stage {
+        Some(stage) => (tmp_dir.join(format!("stage-{stage}")), stage),
+        None => (tmp_dir.to_path_buf(), Stage::ALL.len()),
+    };
+
+    // Clear out the results of any previous run.
+    fs::create_dir_all(&processed_dir)?;
+    for entry in fs::read_dir(&processed_dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "md") {
+            fs::remove_file(path)?;
+        }
+    }
+
+    for entry in walkdir::WalkDir::new(&markdown_dir) {
+        let entry = entry?;
+        let path = entry.path();
+        if !entry.file_type().is_file()
+            || path.extension().is_none_or(|ext| ext != "md")
+        {
+            continue;
+        }
+
+        let input = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read '{}': {e}", path.display()))?;
+        let output = pipeline.run_through(&input, last_stage);
+
+        // mdBook mirrors the `src` directory layout, but every file name in
+        // the book is unique, so flatten them into one directory.
+        let file_name = path.file_name().expect("file should have a name");
+        fs::write(processed_dir.join(file_name), output)?;
+    }
+
+    if let Some(stage) = args.flag_dump_stage {
+        let name = match stage {
+            0 => "rendered Markdown",
+            n => Stage::ALL[n - 1].name(),
+        };
+        println!(
+            "Wrote files as of stage {stage} ({name}) to {}",
+            processed_dir.display()
+        );
+        return Ok(());
+    }
+
+    let summary = fs::read_to_string(&args.flag_summary).map_err(|e| {
+        format!("Could not read summary '{}': {e}", args.flag_summary)
+    })?;
+    let summary = Summary::parse(&summary)?;
+
+    let out_dir = Path::new(&args.flag_out_dir);
+    fs::create_dir_all(out_dir)?;
+    concat_chapter_files(
+        &summary,
+        &ConcatOptions::default(),
+        &processed_dir,
+        out_dir,
+    )?;
+
+    Ok(())
+}
+
+const USAGE: &str = "
+Export the book as Markdown for No Starch Press.
+
+Stages, in the order they run:
+  1. remove_links
+  2. link2print
+  3. remove_markup
+  4. remove_hidden_lines
+  5. cleanup_blockquotes
+
+Usage:
+  export_nostarch [options]
+  export_nostarch (-h | --help)
+
+Options:
+  --book-dir=<dir>          Directory with the mdBook config to render with.
+                            [default: nostarch]
+  --tmp-dir=<dir>           Directory mdBook renders into, and where processed
+                            files are written. Must match the `build-dir` in
+                            the mdBook config. [default: tmp]
+  --out-dir=<dir>           Directory for the concatenated chapters.
+                            [default: nostarch]
+  --summary=<path>          The summary describing how to group the files into
+                            chapters. [default: src/SUMMARY.md]
+  --skip-build              Use the Markdown already in <tmp-dir>/markdown
+                            instead of running mdBook.
+  --no-remove-links         Disable the remove_links stage.
+  --no-link2print           Disable the link2print stage.
+  --no-remove-markup        Disable the remove_markup stage.
+  --no-remove-hidden-lines  Disable the remove_hidden_lines stage.
+  --no-cleanup-blockquotes  Disable the cleanup_blockquotes stage.
+  --dump-stage=<n>          Write each file as it is after stage <n> (0 for the
+                            rendered Markdown) to <tmp-dir>/stage-<n> and stop
+                            without concatenating.
+  -h --help                 Show this screen.
+";
+
+#[derive(Debug, Deserialize)]
+struct Args {
+    flag_book_dir: String,
+    flag_tmp_dir: String,
+    flag_out_dir: String,
+    flag_summary: String,
+    flag_skip_build: bool,
+    flag_no_remove_links: bool,
+    flag_no_link2print: bool,
+    flag_no_remove_markup: bool,
+    flag_no_remove_hidden_lines: bool,
+    flag_no_cleanup_blockquotes: bool,
+    flag_dump_stage: Option<usize>,
+}
+
+/// Render the book as Markdown to include all the code listings.
+fn render_markdown(
+    book_dir: &str,
+    markdown_dir: &Path,
+) -> Result<(), Box<dyn Error>> {
+    if markdown_dir.is_dir() {
+        fs::remove_dir_all(markdown_dir)?;
+    }
+
+    let status = Command::new("mdbook")
+        .args(["build", book_dir])
+        .env("MDBOOK_OUTPUT__MARKDOWN", "{}")
+        .status()
+        .map_err(|e| format!("Could not run mdbook: {e}"))?;
+
+    if !status.success() {
+        return Err(format!("mdbook build {book_dir} failed: {status}").into());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,71 @@
+//! Run `cargo fmt` on every listing whose `listing.toml` allows it; see
+//! [`rust_book_tools::manifest::Manifest::rustfmt`].
+
+use std::error::Error;
+use std::process::Command;
+
+use docopt::Docopt;
+use rust_book_tools::manifest::Manifest;
+use serde::Deserialize;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Args = Docopt::new(USAGE)
+        .and_then(|d| d.deserialize())
+        .unwrap_or_else(|e| e.exit());
+
+    let mut failures = 0;
+    for entry in walkdir::WalkDir::new(&args.flag_listings_dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| entry.file_name() != "target")
+    {
+        let entry = entry?;
+        if entry.file_name() != "Cargo.toml" {
+            continue;
+        }
+        let dir = entry.path().parent().expect("Cargo.toml has a parent");
+        if !Manifest::load(dir)?.rustfmt {
+            println!("Skipping {}", dir.display());
+            continue;
+        }
+
+        // A listing which doesn't parse can't be formatted, but that's no
+        // reason to stop formatting the rest.
+        let status = Command::new("cargo")
+            .args(["fmt", "--all"])
+            .current_dir(dir)
+            .status()
+            .map_err(|e| format!("Could not run cargo fmt: {e}"))?;
+        if !status.success() {
+            eprintln!("Could not format {}", dir.display());
+            failures += 1;
+        }
+    }
+
+    if failures > 0 {
+        eprintln!("{failures} listings could not be formatted");
+    }
+    Ok(())
+}
+
+const USAGE: &str = "
+Run `cargo fmt` on every listing, except those whose listing.toml has
+`rustfmt = false`.
+
+Listings which rustfmt can't format are reported, but don't stop the rest from
+being formatted.
+
+Usage:
+  format_listings [options]
+  format_listings (-h | --help)
+
+Options:
+  --listings-dir=<dir>  Directory with the listings to format.
+                        [default: listings]
+  -h --help             Show this screen.
+";
+
+#[derive(Debug, Deserialize)]
+struct Args {
+    flag_listings_dir: String,
+}
@@ -1,10 +1,10 @@
-// We have some long regex literals, so:
-// ignore-tidy-linelength
+//! Lint the book's Markdown and listing output; see [`rust_book_tools::lint`].
 
-use std::io::BufRead;
-use std::{fs, io, path};
+use std::collections::BTreeMap;
+use std::{fs, path};
 
 use docopt::Docopt;
+use rust_book_tools::lint::{self, Config, FileKind, Level, RULES};
 use serde::Deserialize;
 
 fn main() {
@@ -12,240 +12,110 @@ fn main() {
         .and_then(|d| d.deserialize())
         .unwrap_or_else(|e| e.exit());
 
-    let src_dir = &path::Path::new(&args.arg_src_dir);
-    let found_errs = walkdir::WalkDir::new(src_dir)
-        .min_depth(1)
-        .into_iter()
-        .map(|entry| match entry {
-            Ok(entry) => entry,
-            Err(err) => {
+    if args.flag_list {
+        for rule in RULES {
+            println!(
+                "{:<20} {:<8} {}",
+                rule.name, rule.default_level, rule.description
+            );
+        }
+        return;
+    }
+
+    let config = config(&args).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        std::process::exit(2)
+    });
+
+    let mut counts: BTreeMap<(&str, Level), usize> = BTreeMap::new();
+    let mut files_checked = 0;
+    for src_dir in &args.arg_src_dir {
+        for entry in walkdir::WalkDir::new(path::Path::new(src_dir))
+            .min_depth(1)
+            .sort_by_file_name()
+        {
+            let entry = entry.unwrap_or_else(|err| {
                 eprintln!("{err:?}");
                 std::process::exit(911)
-            }
-        })
-        .map(|entry| {
+            });
             let path = entry.path();
-            if is_file_of_interest(path) {
-                let err_vec = lint_file(path);
-                for err in &err_vec {
-                    match *err {
-                        LintingError::LineOfInterest(line_num, ref line) => {
-                            eprintln!(
-                                "{}:{}\t{}",
-                                path.display(),
-                                line_num,
-                                line
-                            )
-                        }
-                        LintingError::UnableToOpenFile => {
-                            eprintln!("Unable to open {}.", path.display())
-                        }
-                    }
-                }
-                !err_vec.is_empty()
-            } else {
-                false
+            // Skip build output from listings which have been compiled.
+            if path.components().any(|c| c.as_os_str() == "target") {
+                continue;
             }
-        })
-        .collect::<Vec<_>>()
-        .iter()
-        .any(|result| *result);
+            let Some(kind) = FileKind::of(path) else {
+                continue;
+            };
+
+            let Ok(text) = fs::read_to_string(path) else {
+                eprintln!("Unable to open {}.", path.display());
+                *counts.entry(("unreadable", Level::Deny)).or_default() += 1;
+                continue;
+            };
+
+            files_checked += 1;
+            for violation in lint::lint(&text, kind, &config) {
+                eprintln!(
+                    "{}:{}: {}[{}]: {}",
+                    path.display(),
+                    violation.finding.line_num,
+                    violation.level,
+                    violation.rule,
+                    violation.finding.message,
+                );
+                *counts
+                    .entry((violation.rule, violation.level))
+                    .or_default() += 1;
+            }
+        }
+    }
+
+    eprintln!("\nChecked {files_checked} files.");
+    for ((rule, level), count) in &counts {
+        eprintln!("{count:>6} {level}[{rule}]");
+    }
 
-    if found_errs {
+    if counts.keys().any(|&(_, level)| level == Level::Deny) {
         std::process::exit(1)
-    } else {
-        std::process::exit(0)
     }
 }
 
 const USAGE: &str = "
-counter
+Lint Markdown files and listing output.txt files for machine-specific paths,
+non-normalized output, and whitespace problems.
+
 Usage:
-  lfp <src-dir>
+  lfp [options] <src-dir>...
+  lfp --list
   lfp (-h | --help)
+
 Options:
-  -h --help         Show this screen.
+  -A <rules>, --allow=<rules>  Comma-separated rules to turn off.
+  -W <rules>, --warn=<rules>   Comma-separated rules to report without failing.
+  -D <rules>, --deny=<rules>   Comma-separated rules to fail on.
+  --list                       List the rules and their default levels.
+  -h --help                    Show this screen.
 ";
 
 #[derive(Debug, Deserialize)]
 struct Args {
-    arg_src_dir: String,
-}
-
-fn lint_file(path: &path::Path) -> Vec<LintingError> {
-    match fs::File::open(path) {
-        Ok(file) => lint_lines(io::BufReader::new(&file).lines()),
-        Err(_) => vec![LintingError::UnableToOpenFile],
-    }
+    arg_src_dir: Vec<String>,
+    flag_allow: Option<String>,
+    flag_warn: Option<String>,
+    flag_deny: Option<String>,
+    flag_list: bool,
 }
 
-fn lint_lines<I>(lines: I) -> Vec<LintingError>
-where
-    I: Iterator<Item = io::Result<String>>,
-{
-    lines
-        .enumerate()
-        .map(|(line_num, line)| {
-            let raw_line = line.unwrap();
-            if is_line_of_interest(&raw_line) {
-                Err(LintingError::LineOfInterest(line_num, raw_line))
-            } else {
-                Ok(())
-            }
-        })
-        .filter(|result| result.is_err())
-        .map(|result| result.unwrap_err())
-        .collect()
-}
-
-fn is_file_of_interest(path: &path::Path) -> bool {
-    path.extension().map_or(false, |ext| ext == "md")
-}
-
-fn is_line_of_interest(line: &str) -> bool {
-    line.split_whitespace().any(|sub_string| {
-        sub_string.contains("file://")
-            && !sub_string.contains("file:///projects/")
-            && !sub_string.contains("file:///home/.cargo")
-            && !sub_string.contains("file:///home/.rustup")
-    })
-}
-
-#[derive(Debug)]
-enum LintingError {
-    UnableToOpenFile,
-    LineOfInterest(usize, String),
-}
-
-#[cfg(test)]
-mod tests {
-
-    use std::path;
-
-    #[test]
-    fn lint_file_returns_a_vec_with_errs_when_lines_of_interest_are_found() {
-        let string = r#"
-        $ cargo run
-               Compiling guessing_game v0.1.0 (file:///home/you/projects/guessing_game)
-                 Running `target/guessing_game`
-            Guess the number!
-            The secret number is: 61
-            Please input your guess.
-            10
-            You guessed: 10
-            Too small!
-            Please input your guess.
-            99
-            You guessed: 99
-            Too big!
-            Please input your guess.
-            foo
-            Please input your guess.
-            61
-            You guessed: 61
-            You win!
-            $ cargo run
-               Compiling guessing_game v0.1.0 (file:///home/you/projects/guessing_game)
-                 Running `target/debug/guessing_game`
-            Guess the number!
-            The secret number is: 7
-            Please input your guess.
-            4
-            You guessed: 4
-            $ cargo run
-                 Running `target/debug/guessing_game`
-            Guess the number!
-            The secret number is: 83
-            Please input your guess.
-            5
-            $ cargo run
-               Compiling guessing_game v0.1.0 (file:///home/you/projects/guessing_game)
-                 Running `target/debug/guessing_game`
-            Hello, world!
-        "#;
-
-        let raw_lines = string.to_string();
-        let lines = raw_lines.lines().map(|line| Ok(line.to_string()));
-
-        let result_vec = super::lint_lines(lines);
-
-        assert!(!result_vec.is_empty());
-        assert_eq!(3, result_vec.len());
-    }
-
-    #[test]
-    fn lint_file_returns_an_empty_vec_when_no_lines_of_interest_are_found() {
-        let string = r#"
-            $ cargo run
-               Compiling guessing_game v0.1.0 (file:///projects/guessing_game)
-                 Running `target/guessing_game`
-            Guess the number!
-            The secret number is: 61
-            Please input your guess.
-            10
-            You guessed: 10
-            Too small!
-            Please input your guess.
-            99
-            You guessed: 99
-            Too big!
-            Please input your guess.
-            foo
-            Please input your guess.
-            61
-            You guessed: 61
-            You win!
-        "#;
-
-        let raw_lines = string.to_string();
-        let lines = raw_lines.lines().map(|line| Ok(line.to_string()));
-
-        let result_vec = super::lint_lines(lines);
-
-        assert!(result_vec.is_empty());
-    }
-
-    #[test]
-    fn is_file_of_interest_returns_false_when_the_path_is_a_directory() {
-        let uninteresting_fn = "src/img";
-
-        assert!(!super::is_file_of_interest(path::Path::new(
-            uninteresting_fn
-        )));
-    }
-
-    #[test]
-    fn is_file_of_interest_returns_false_when_the_filename_does_not_have_the_md_extension()
-     {
-        let uninteresting_fn = "src/img/foo1.png";
-
-        assert!(!super::is_file_of_interest(path::Path::new(
-            uninteresting_fn
-        )));
-    }
-
-    #[test]
-    fn is_file_of_interest_returns_true_when_the_filename_has_the_md_extension()
-    {
-        let interesting_fn = "src/ch01-00-introduction.md";
-
-        assert!(super::is_file_of_interest(path::Path::new(interesting_fn)));
-    }
-
-    #[test]
-    fn is_line_of_interest_does_not_report_a_line_if_the_line_contains_a_file_url_which_is_directly_followed_by_the_project_path()
-     {
-        let sample_line =
-            "Compiling guessing_game v0.1.0 (file:///projects/guessing_game)";
-
-        assert!(!super::is_line_of_interest(sample_line));
-    }
-
-    #[test]
-    fn is_line_of_interest_reports_a_line_if_the_line_contains_a_file_url_which_is_not_directly_followed_by_the_project_path()
-     {
-        let sample_line = "Compiling guessing_game v0.1.0 (file:///home/you/projects/guessing_game)";
-
-        assert!(super::is_line_of_interest(sample_line));
+fn config(args: &Args) -> Result<Config, String> {
+    let mut config = Config::default();
+    for (rules, level) in [
+        (&args.flag_allow, Level::Allow),
+        (&args.flag_warn, Level::Warn),
+        (&args.flag_deny, Level::Deny),
+    ] {
+        for rule in rules.iter().flat_map(|rules| rules.split(',')) {
+            config.set(rule.trim(), level)?;
+        }
     }
+    Ok(config)
 }
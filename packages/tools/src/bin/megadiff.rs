@@ -0,0 +1,57 @@
+//! Compare two builds of the book and write a report of the pages which
+//! changed; see [`rust_book_tools::megadiff`].
+
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use docopt::Docopt;
+use rust_book_tools::megadiff::Report;
+use serde::Deserialize;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Args = Docopt::new(USAGE)
+        .and_then(|d| d.deserialize())
+        .unwrap_or_else(|e| e.exit());
+
+    let report =
+        Report::new(Path::new(&args.flag_before), Path::new(&args.flag_after))?;
+
+    let output = Path::new(&args.flag_output);
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(output, report.to_string())
+        .map_err(|e| format!("Could not write '{}': {e}", output.display()))?;
+
+    println!("{}", report.summary());
+    println!("Report written to {}", output.display());
+    Ok(())
+}
+
+const USAGE: &str = "
+Compare two builds of the book, like the ones ./tools/update-rustc.sh makes
+before and after updating the toolchain.
+
+Only the content of each HTML page is compared, leaving out the search index,
+print.html, and assets. Pages are listed by whether their code blocks, their
+prose, or only whitespace changed, and the report ends with a diff of the code
+blocks and output on each page whose code changed.
+
+Usage:
+  megadiff [options]
+  megadiff (-h | --help)
+
+Options:
+  --before=<dir>   The book built before the change. [default: tmp/book-before]
+  --after=<dir>    The book built after the change. [default: tmp/book-after]
+  --output=<file>  Where to write the report. [default: tmp/megadiff.txt]
+  -h --help        Show this screen.
+";
+
+#[derive(Debug, Deserialize)]
+struct Args {
+    flag_before: String,
+    flag_after: String,
+    flag_output: String,
+}
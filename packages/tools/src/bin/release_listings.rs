@@ -1,206 +1,149 @@
+//! Produce the archive of cleaned listings published with each release; see
+//! [`rust_book_tools::release`].
+
 use std::error::Error;
-use std::fs;
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::{BufReader, BufWriter};
 use std::path::{Path, PathBuf};
 
-use lazy_static::lazy_static;
-use regex::Regex;
+use docopt::Docopt;
+use rust_book_tools::release::{self, Filter, Format, Trpl};
+use serde::Deserialize;
 
 fn main() -> Result<(), Box<dyn Error>> {
-    // Get all listings from the `listings` directory
-    let listings_dir = Path::new("listings");
-
-    // Put the results in the `tmp/listings` directory
-    let out_dir = Path::new("tmp/listings");
-
-    // Clear out any existing content in `tmp/listings`
-    if out_dir.is_dir() {
-        fs::remove_dir_all(out_dir)?;
-    }
-
-    // Create a new, empty `tmp/listings` directory
-    fs::create_dir(out_dir)?;
-
-    // For each chapter in the `listings` directory,
-    for chapter in fs::read_dir(listings_dir)? {
-        let chapter = chapter?;
-        let chapter_path = chapter.path();
-
-        if !chapter_path.is_dir() {
-            eprintln!(
-                "'{}' is not a directory, skipping",
-                chapter_path.display()
-            );
-            continue;
+    let args: Args = Docopt::new(USAGE)
+        .and_then(|d| d.deserialize())
+        .unwrap_or_else(|e| e.exit());
+
+    let listings_dir = Path::new(&args.flag_listings_dir);
+    let out_dir = Path::new(&args.flag_out_dir);
+    let format = Format::from_name(&args.flag_format)?;
+    let filter = Filter {
+        exclude_output_only: args.flag_exclude_output_only,
+    };
+
+    let trpl =
+        Trpl::from_name(&args.flag_trpl, Path::new(&args.flag_trpl_dir))?;
+
+    let chapters =
+        release::clean_listings(listings_dir, out_dir, filter, &trpl)?;
+
+    if args.flag_verify {
+        let does_not_compile =
+            release::does_not_compile_in_book(Path::new(&args.flag_src_dir))?;
+        let (checked, breakages) = release::verify(
+            listings_dir,
+            out_dir,
+            Path::new(&args.flag_target_dir),
+            &does_not_compile,
+        )?;
+        for breakage in &breakages {
+            eprintln!("{}: {}", breakage.project, breakage.problem);
         }
-
-        let chapter_name = chapter_path
-            .file_name()
-            .expect("Chapter should've had a name");
-
-        // Create a corresponding chapter dir in `tmp/listings`
-        let output_chapter_path = out_dir.join(chapter_name);
-        fs::create_dir(&output_chapter_path).map_err(|e| {
-            format!(
-                "could not create dir at '{}': {e}",
-                output_chapter_path.display()
-            )
-        })?;
-
-        // For each listing in the chapter directory,
-        for listing in fs::read_dir(&chapter_path).map_err(|e| {
-            format!("Could not read '{}': {e}", chapter_path.display())
-        })? {
-            let listing = listing.map_err(|e| {
-                format!(
-                    "bad dir entry listing in {}: {e}",
-                    chapter_path.display()
-                )
-            })?;
-            let listing_path = listing.path();
-
-            if !listing_path.is_dir() {
-                eprintln!(
-                    "'{}' is not a directory, skipping",
-                    listing_path.display(),
-                );
-                continue;
-            }
-
-            let listing_name = listing_path
-                .file_name()
-                .expect("Listing should've had a name");
-
-            // Create a corresponding listing dir in the tmp chapter dir
-            let output_listing_dir = output_chapter_path.join(listing_name);
-            fs::create_dir(&output_listing_dir).map_err(|e| {
-                format!(
-                    "could not create dir '{}': {e}",
-                    output_listing_dir.display()
-                )
-            })?;
-
-            // Copy all the cleaned files in the listing to the tmp directory
-            copy_cleaned_listing_files(listing_path, output_listing_dir)?;
+        println!(
+            "Verified {checked} cleaned listings: {} problems",
+            breakages.len()
+        );
+        if !breakages.is_empty() {
+            return Err(
+                "Some cleaned listings are broken; not archiving them".into()
+            );
         }
     }
 
     // Create a compressed archive of all the listings
-    let tarfile = File::create("tmp/listings.tar.gz")?;
-    let encoder =
-        flate2::write::GzEncoder::new(tarfile, flate2::Compression::default());
-    let mut archive = tar::Builder::new(encoder);
-    archive.append_dir_all("listings", "tmp/listings")?;
+    let archive = args.flag_archive.map_or_else(
+        || PathBuf::from(format!("tmp/listings.{}", format.extension())),
+        PathBuf::from,
+    );
+    release::write_archive(out_dir, "listings", &archive, format)?;
 
     // Assure whoever is running this that the script exiting successfully, and remind them
     // where the generated file ends up
-    println!("Release tarball of listings in tmp/listings.tar.gz");
-
-    Ok(())
-}
-
-// Cleaned listings will not contain:
-//
-// - `target` directories
-// - `output.txt` files used to display output in the book
-// - `rustfmt-ignore` files used to signal to update-rustc.sh the listing shouldn't be formatted
-// - anchor comments or snip comments
-// - empty `main` functions in `lib.rs` files used to trick rustdoc
-fn copy_cleaned_listing_files(
-    from: PathBuf,
-    to: PathBuf,
-) -> Result<(), Box<dyn Error>> {
-    for item in fs::read_dir(&from).map_err(|e| {
-        format!("Could not read_dir on '{}': {e}", from.display())
-    })? {
-        let item = item.map_err(|e| {
-            format!("invalid dir entry in {}: {e}", from.display())
-        })?;
-        let item_path = item.path();
-
-        let item_name =
-            item_path.file_name().expect("Item should've had a name");
-        let output_item = to.join(item_name);
-
-        if item_path.is_dir() {
-            // Don't copy `target` directories
-            if item_name != "target" {
-                fs::create_dir(&output_item).map_err(|e| {
-                    format!(
-                        "Could not create output directory '{}': {e}",
-                        output_item.display()
-                    )
-                })?;
-                copy_cleaned_listing_files(item_path, output_item)?;
-            }
-        } else {
-            // Don't copy output files or files that tell update-rustc.sh not to format
-            if item_name != "output.txt" && item_name != "rustfmt-ignore" {
-                let item_extension = item_path.extension();
-                if item_extension.is_some() && item_extension.unwrap() == "rs" {
-                    copy_cleaned_rust_file(
-                        item_name,
-                        &item_path,
-                        &output_item,
-                    )?;
-                } else {
-                    // Copy any non-Rust files without modification
-                    fs::copy(&item_path, &output_item).map_err(|e| {
-                        format!(
-                            "Could not copy from '{}' to '{}': {e}",
-                            item_path.display(),
-                            output_item.display()
-                        )
-                    })?;
-                }
-            }
+    println!("Release archive of listings in {}", archive.display());
+
+    if let Some(chapter_dir) = args.flag_per_chapter {
+        let chapter_dir = Path::new(&chapter_dir);
+        for chapter in &chapters {
+            let archive =
+                chapter_dir.join(format!("{chapter}.{}", format.extension()));
+            release::write_archive(
+                &out_dir.join(chapter),
+                chapter,
+                &archive,
+                format,
+            )?;
         }
+        println!(
+            "Release archives of each chapter's listings in {}",
+            chapter_dir.display()
+        );
     }
 
     Ok(())
 }
 
-lazy_static! {
-    static ref ANCHOR_OR_SNIP_COMMENTS: Regex = Regex::new(
-        r"(?x)
-    //\s*ANCHOR:\s*[\w_-]+      # Remove all anchor comments
-    |
-    //\s*ANCHOR_END:\s*[\w_-]+  # Remove all anchor ending comments
-    |
-    //\s*--snip--               # Remove all snip comments
-    "
-    )
-    .unwrap();
-}
-
-lazy_static! {
-    static ref EMPTY_MAIN: Regex = Regex::new(r"fn main\(\) \{}").unwrap();
-}
-
-// Cleaned Rust files will not contain:
-//
-// - anchor comments or snip comments
-// - empty `main` functions in `lib.rs` files used to trick rustdoc
-fn copy_cleaned_rust_file(
-    item_name: &std::ffi::OsStr,
-    from: &PathBuf,
-    to: &PathBuf,
-) -> Result<(), Box<dyn Error>> {
-    let from_buf = BufReader::new(File::open(from)?);
-    let mut to_buf = BufWriter::new(File::create(to)?);
-
-    for line in from_buf.lines() {
-        let line = line?;
-        if !ANCHOR_OR_SNIP_COMMENTS.is_match(&line)
-            && (item_name != "lib.rs" || !EMPTY_MAIN.is_match(&line))
-        {
-            writeln!(&mut to_buf, "{line}")?;
-        }
-    }
-
-    to_buf.flush()?;
-
-    Ok(())
+const USAGE: &str = "
+Copy the listings without the comments and files that are only there for the
+book, and archive them for a release.
+
+The archives are reproducible: building them again from the same listings
+gives the same bytes, so releases can be diffed and checksummed.
+
+With --verify, the cleaned listings are checked before they are archived: their
+path dependencies must be part of the release, and each one which is expected
+to compile is checked with `cargo check --offline`. A listing is expected to
+compile unless its listing.toml or output.txt says it fails, or the book shows
+it in a `does_not_compile` code block.
+
+The async chapter's listings depend on the book's `trpl` crate by a path inside
+this repository, which doesn't exist once they're downloaded. The --trpl option
+says what to do about that: `path` leaves the dependency alone, which only
+works for building the cleaned listings here; `crates-io` depends on the
+published version of the crate in the --trpl-dir directory, which must already
+be on crates.io, and refuses if its CHANGELOG.md lists unreleased changes; and
+`bundle` copies that crate into each chapter which uses it, and depends on the
+copy.
+
+Usage:
+  release_listings [options]
+  release_listings (-h | --help)
+
+Options:
+  --listings-dir=<dir>   Directory with the listings to release.
+                         [default: listings]
+  --out-dir=<dir>        Where to put the cleaned listings, which is emptied
+                         first. [default: tmp/listings]
+  --format=<format>      The kind of archive to make: tar.gz or zip.
+                         [default: tar.gz]
+  --archive=<file>       Where to write the archive of every listing. Defaults
+                         to tmp/listings.tar.gz or tmp/listings.zip.
+  --per-chapter=<dir>    Also write an archive of each chapter's listings to
+                         this directory, named after the chapter.
+  --exclude-output-only  Leave out the output-only-* listings, which only exist
+                         to produce output shown in the book.
+  --trpl=<mode>          How listings depend on trpl: path, crates-io, or
+                         bundle. [default: path]
+  --trpl-dir=<dir>       The trpl crate to depend on or bundle.
+                         [default: packages/trpl]
+  --verify               Check that the cleaned listings still work on their
+                         own, and don't archive them if they don't.
+  --src-dir=<dir>        The book's Markdown, for which listings it says do
+                         not compile. [default: src]
+  --target-dir=<dir>     Where to put build artifacts when verifying.
+                         [default: tmp/verify-target]
+  -h --help              Show this screen.
+";
+
+#[derive(Debug, Deserialize)]
+struct Args {
+    flag_listings_dir: String,
+    flag_out_dir: String,
+    flag_format: String,
+    flag_archive: Option<String>,
+    flag_per_chapter: Option<String>,
+    flag_exclude_output_only: bool,
+    flag_trpl: String,
+    flag_trpl_dir: String,
+    flag_verify: bool,
+    flag_src_dir: String,
+    flag_target_dir: String,
 }
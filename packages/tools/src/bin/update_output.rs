@@ -0,0 +1,374 @@
+//! Regenerate the `output.txt` files in the listings; see
+//! [`rust_book_tools::output`].
+//!
+//! This expects the cleaned listings produced by `release_listings` to be in
+//! the build directory, with the `trpl` crate built alongside them so the
+//! listings which depend on it can find it.
+//!
+//! Listings whose sources, toolchain, and output have not changed since the
+//! last run are skipped, except with `--check`, and the rest are built in
+//! parallel.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, mpsc};
+use std::thread;
+
+use docopt::Docopt;
+use rust_book_tools::manifest::Manifest;
+use rust_book_tools::output::{self, Cache, TargetDir};
+use serde::Deserialize;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Args = Docopt::new(USAGE)
+        .and_then(|d| d.deserialize())
+        .unwrap_or_else(|e| e.exit());
+
+    let listings_dir = Path::new(&args.flag_listings_dir);
+    let build_dir = Path::new(&args.flag_build_dir);
+    let cache_path = Path::new(&args.flag_cache);
+    let shared_target_dir = Path::new(&args.flag_target_dir);
+
+    let jobs = match args.flag_jobs {
+        Some(0) => return Err("--jobs must be at least 1".into()),
+        Some(jobs) => jobs,
+        None => thread::available_parallelism().map_or(1, |n| n.get()),
+    };
+
+    let trpl_hash = output::hash_dir(Path::new(&args.flag_trpl_dir))?;
+    let mut toolchains = HashMap::new();
+    // Checking must build every listing: a cache entry only says the output
+    // matched when it was recorded, not that it still does.
+    let mut cache = if args.flag_force || args.flag_check {
+        Cache::default()
+    } else {
+        Cache::load(cache_path)?
+    };
+
+    let mut listings = Vec::new();
+    let mut unchanged = 0;
+    for output_path in output_files(listings_dir, &args.arg_listing)? {
+        let listing =
+            Listing::new(&output_path, listings_dir, build_dir, &trpl_hash)?;
+
+        let toolchain = match toolchains.get(&listing.toolchain) {
+            Some(version) => version,
+            None => {
+                let version =
+                    output::toolchain_version(&listing.command, build_dir)?;
+                toolchains
+                    .entry(listing.toolchain.clone())
+                    .or_insert(version)
+            }
+        };
+
+        if cache.is_fresh(
+            &listing.name,
+            &listing.hash(toolchain, &listing.previous),
+        ) {
+            unchanged += 1;
+            continue;
+        }
+        listings.push((listing, toolchain.clone()));
+    }
+
+    println!(
+        "{} {} listings with {jobs} jobs ({unchanged} unchanged)",
+        if args.flag_check {
+            "Checking"
+        } else {
+            "Regenerating"
+        },
+        listings.len()
+    );
+
+    // Each worker gets its own shared target directory, because two builds
+    // waiting on each other's lock would change the output. Start from
+    // scratch each run so every listing sharing one is compiled in full.
+    if shared_target_dir.is_dir() {
+        fs::remove_dir_all(shared_target_dir)?;
+    }
+
+    let queue = Mutex::new(listings.into_iter());
+    let (sender, receiver) = mpsc::channel();
+    let mut failures = 0;
+    let mut drifted = 0;
+    thread::scope(|scope| {
+        for worker in 0..jobs {
+            let queue = &queue;
+            let sender = sender.clone();
+            let worker_dir = shared_target_dir.join(worker.to_string());
+            scope.spawn(move || {
+                // How many packages of each name this worker has built, so
+                // that packages with the same name get different directories.
+                let mut built: HashMap<String, usize> = HashMap::new();
+                loop {
+                    let next = queue.lock().unwrap().next();
+                    let Some((listing, toolchain)) = next else {
+                        break;
+                    };
+                    let shared_dir =
+                        listing.shared_package.as_ref().map(|name| {
+                            let count = built.entry(name.clone()).or_default();
+                            *count += 1;
+                            worker_dir.join(count.to_string())
+                        });
+                    let target_dir = match &shared_dir {
+                        Some(dir) => TargetDir::Shared(dir),
+                        None => TargetDir::Own,
+                    };
+                    let result = output::regenerate(
+                        &listing.previous,
+                        &listing.build_dir,
+                        target_dir,
+                        &listing.manifest,
+                    )
+                    .map_err(|e| e.to_string());
+                    if sender.send((listing, toolchain, result)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        // Handle the results on this thread as they come in, so the cache
+        // keeps whatever finished even if something fails along the way.
+        for (listing, toolchain, result) in receiver {
+            let path = listing.output_path.display();
+            let new_output = match result {
+                Ok(new_output) => new_output,
+                Err(e) => {
+                    eprintln!("Could not regenerate {path}: {e}");
+                    failures += 1;
+                    continue;
+                }
+            };
+
+            if args.flag_check {
+                match output::drift(
+                    &listing.output_path,
+                    &listing.previous,
+                    &new_output,
+                ) {
+                    Some(diff) => {
+                        println!("Output of {path} has drifted:\n{diff}");
+                        drifted += 1;
+                    }
+                    None => println!("Checked {path}"),
+                }
+                continue;
+            }
+
+            if let Err(e) = fs::write(&listing.output_path, &new_output) {
+                eprintln!("Could not write {path}: {e}");
+                failures += 1;
+                continue;
+            }
+            println!("Regenerated {path}");
+            cache.insert(&listing.name, listing.hash(&toolchain, &new_output));
+        }
+    });
+
+    if shared_target_dir.is_dir() {
+        fs::remove_dir_all(shared_target_dir)?;
+    }
+    if !args.flag_check {
+        cache.save(cache_path)?;
+    }
+
+    if failures > 0 {
+        return Err(format!("{failures} listings failed to regenerate").into());
+    }
+    if drifted > 0 {
+        return Err(format!(
+            "The output of {drifted} listings does not match what is checked \
+            in; run update_output to regenerate it"
+        )
+        .into());
+    }
+    Ok(())
+}
+
+const USAGE: &str = "
+Regenerate the output.txt files in the listings.
+
+Each output.txt starts with the command to run, like `$ cargo run`, unless the
+listing's listing.toml says otherwise, and may show more than one. The commands
+are run in the listing's copy in the build directory, with any input from its
+listing.toml, and their output is normalized so it does not depend on the
+machine it ran on.
+
+Listings are skipped if nothing that affects their output has changed since
+they were last regenerated: their files, the toolchain, the trpl crate (for
+listings which use it), or the output.txt itself.
+
+With --check, nothing is written and the cache is ignored, so every listing is
+built. Instead, a diff is printed for each listing whose output no longer
+matches what is checked in, and the exit status is non-zero if there are any.
+
+Usage:
+  update_output [options] [<listing>...]
+  update_output (-h | --help)
+
+Options:
+  --listings-dir=<dir>  Directory with the listings whose output to update.
+                        [default: listings]
+  --build-dir=<dir>     Directory with the cleaned copies of the listings to
+                        build and run. [default: tmp/listings]
+  --trpl-dir=<dir>      The trpl crate the listings depend on.
+                        [default: packages/trpl]
+  --cache=<file>        Where to record what each output was generated from.
+                        [default: tmp/output-cache]
+  --target-dir=<dir>    Directory for the target directories shared by
+                        listings without dependencies. [default: tmp/target]
+  -j --jobs=<n>         How many listings to build at once. Defaults to the
+                        number of CPUs.
+  -f --force            Regenerate every listing, even unchanged ones.
+  --check               Report listings whose output has drifted instead of
+                        updating them, without skipping unchanged ones.
+  -h --help             Show this screen.
+
+Arguments:
+  <listing>  Only update listings whose path contains one of these, e.g.
+             `ch02` or `listing-11-03`.
+";
+
+#[derive(Debug, Deserialize)]
+struct Args {
+    flag_listings_dir: String,
+    flag_build_dir: String,
+    flag_trpl_dir: String,
+    flag_cache: String,
+    flag_target_dir: String,
+    flag_jobs: Option<usize>,
+    flag_force: bool,
+    flag_check: bool,
+    arg_listing: Vec<String>,
+}
+
+/// A listing whose output to regenerate.
+struct Listing {
+    /// The listing's path relative to the listings directory, which is also
+    /// its key in the cache.
+    name: String,
+    output_path: PathBuf,
+    build_dir: PathBuf,
+    previous: String,
+    manifest: Manifest,
+    /// The command to get the toolchain version with: the first one which
+    /// asks for a `+toolchain`, if any do.
+    command: String,
+    /// The `+toolchain` the commands ask for, if any.
+    toolchain: Option<String>,
+    /// The listing's package, if it can share a target directory.
+    shared_package: Option<String>,
+    sources_hash: String,
+    trpl_hash: Option<String>,
+}
+
+impl Listing {
+    fn new(
+        output_path: &Path,
+        listings_dir: &Path,
+        build_dir: &Path,
+        trpl_hash: &str,
+    ) -> Result<Listing, Box<dyn Error>> {
+        let listing_dir = output_path
+            .parent()
+            .expect("output.txt should be in a listing directory");
+        let relative = listing_dir
+            .strip_prefix(listings_dir)
+            .expect("listing should be in the listings directory");
+        let build_dir = build_dir.join(relative);
+
+        let previous = fs::read_to_string(output_path).map_err(|e| {
+            format!("Could not read '{}': {e}", output_path.display())
+        })?;
+        let manifest = Manifest::load(listing_dir)?;
+        let commands: Vec<_> = manifest
+            .steps(&output::commands(&previous))
+            .map_err(|e| format!("{e} for '{}'", output_path.display()))?
+            .into_iter()
+            .map(|step| step.command)
+            .chain(manifest.setup.iter().cloned())
+            .collect();
+        let command = commands
+            .iter()
+            .find(|command| {
+                command.split_whitespace().any(|w| w.starts_with('+'))
+            })
+            .unwrap_or(&commands[0])
+            .clone();
+        let toolchain = command
+            .split_whitespace()
+            .find(|word| word.starts_with('+'))
+            .map(String::from);
+
+        let cargo_toml_path = build_dir.join("Cargo.toml");
+        let cargo_toml = fs::read_to_string(&cargo_toml_path).map_err(|e| {
+            format!(
+                "Could not read '{}' (run release_listings first?): {e}",
+                cargo_toml_path.display()
+            )
+        })?;
+
+        Ok(Listing {
+            name: relative.to_string_lossy().into_owned(),
+            output_path: output_path.to_path_buf(),
+            shared_package: output::package_name(&cargo_toml)
+                .filter(|_| {
+                    commands.iter().all(|command| {
+                        output::can_share_target_dir(&cargo_toml, command)
+                    })
+                })
+                .map(String::from),
+            sources_hash: output::hash_dir(listing_dir)?,
+            trpl_hash: cargo_toml
+                .contains("trpl")
+                .then(|| trpl_hash.to_string()),
+            build_dir,
+            previous,
+            manifest,
+            command,
+            toolchain,
+        })
+    }
+
+    fn hash(&self, toolchain: &str, output: &str) -> String {
+        output::listing_hash(
+            &self.sources_hash,
+            toolchain,
+            self.trpl_hash.as_deref(),
+            output,
+        )
+    }
+}
+
+/// Every `output.txt` in `listings_dir`, in a stable order, limited to the
+/// listings matching `filters` if there are any.
+fn output_files(
+    listings_dir: &Path,
+    filters: &[String],
+) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut paths = Vec::new();
+    for entry in walkdir::WalkDir::new(listings_dir).sort_by_file_name() {
+        let entry = entry?;
+        let path = entry.path();
+        if path.components().any(|c| c.as_os_str() == "target")
+            || entry.file_name() != "output.txt"
+        {
+            continue;
+        }
+        let matches = filters.is_empty()
+            || filters
+                .iter()
+                .any(|filter| path.to_string_lossy().contains(filter.as_str()));
+        if matches {
+            paths.push(path.to_path_buf());
+        }
+    }
+    Ok(paths)
+}
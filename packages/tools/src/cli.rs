@@ -0,0 +1,21 @@
+//! Helpers for the binaries which filter Markdown from stdin to stdout.
+
+use std::io::{self, Read};
+
+pub fn read_md() -> String {
+    let mut buffer = String::new();
+    match io::stdin().read_to_string(&mut buffer) {
+        Ok(_) => buffer,
+        Err(error) => panic!("{error}"),
+    }
+}
+
+pub fn write_md(output: &str) {
+    print!("{output}");
+}
+
+/// Read Markdown from stdin, run it through `transform`, and write the result
+/// to stdout.
+pub fn filter_md(transform: impl FnOnce(&str) -> String) {
+    write_md(&transform(&read_md()));
+}
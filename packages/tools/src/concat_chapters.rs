@@ -0,0 +1,794 @@
+//! Concatenate the per-section Markdown files of the book into one file per
+//! chapter, following the structure of `SUMMARY.md`.
+
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, read_dir};
+use std::path::Path;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+/// The structure of the book, as described by mdBook's `SUMMARY.md`.
+#[derive(Debug, PartialEq)]
+pub struct Summary {
+    pub entries: Vec<SummaryEntry>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SummaryEntry {
+    /// An unnumbered chapter before the numbered chapters, like the foreword.
+    Prefix { file: String },
+    /// A part title, which groups the numbered chapters after it.
+    Part { title: String },
+    /// A numbered chapter: the file for the chapter itself followed by the
+    /// files for all of its (possibly nested) sections, in order.
+    Chapter {
+        number: usize,
+        title: String,
+        files: Vec<String>,
+    },
+    /// An unnumbered chapter after the numbered chapters.
+    Suffix { file: String },
+}
+
+lazy_static! {
+    static ref SUMMARY_LINK: Regex =
+        Regex::new(r"^(?P<indent>\s*)(?P<bullet>[-*]\s+)?\[(?P<title>.*)\]\((?P<file>[^)]*)\)\s*$")
+            .unwrap();
+    static ref SUMMARY_HEADING: Regex =
+        Regex::new(r"^#+\s+(?P<title>.*?)\s*$").unwrap();
+}
+
+impl Summary {
+    pub fn parse(text: &str) -> Result<Summary, ConcatError> {
+        let mut entries = Vec::new();
+        let mut seen_book_title = false;
+        let mut seen_numbered = false;
+        let mut number = 0;
+
+        for (index, line) in text.lines().enumerate() {
+            if line.trim().is_empty() || line.trim() == "---" {
+                continue;
+            }
+
+            if let Some(caps) = SUMMARY_HEADING.captures(line) {
+                // The first heading is the title of the book itself; any
+                // later ones are part titles.
+                if seen_book_title {
+                    entries.push(SummaryEntry::Part {
+                        title: caps["title"].to_string(),
+                    });
+                }
+                seen_book_title = true;
+                continue;
+            }
+
+            let Some(caps) = SUMMARY_LINK.captures(line) else {
+                return Err(ConcatError::InvalidSummaryLine {
+                    line_num: index + 1,
+                    line: line.to_string(),
+                });
+            };
+
+            // Draft chapters have no file, and so no content to include.
+            let file = caps["file"].trim();
+            if file.is_empty() {
+                continue;
+            }
+            let file = file_name(file).to_string();
+
+            if caps.name("bullet").is_none() {
+                entries.push(if seen_numbered {
+                    SummaryEntry::Suffix { file }
+                } else {
+                    SummaryEntry::Prefix { file }
+                });
+                continue;
+            }
+
+            seen_numbered = true;
+            if caps["indent"].is_empty() {
+                number += 1;
+                entries.push(SummaryEntry::Chapter {
+                    number,
+                    title: caps["title"].to_string(),
+                    files: vec![file],
+                });
+            } else if let Some(SummaryEntry::Chapter { files, .. }) =
+                entries.last_mut()
+            {
+                files.push(file);
+            } else {
+                return Err(ConcatError::InvalidSummaryLine {
+                    line_num: index + 1,
+                    line: line.to_string(),
+                });
+            }
+        }
+
+        Ok(Summary { entries })
+    }
+}
+
+/// mdBook renders (and we flatten) everything by file name, so the directory
+/// parts of the summary's links do not matter.
+fn file_name(link: &str) -> &str {
+    link.rsplit('/').next().unwrap_or(link)
+}
+
+/// How to group and name the concatenated files.
+///
+/// The name templates may use `{number}` (the chapter or part number, padded
+/// to two digits) and, for appendices, `{letter}` (`a`, `b`, …, `z`, then `aa`,
+/// `ab`, and so on, so no two appendices get the same name).
+///
+/// The defaults produce the files in `nostarch/`, leaving alone the ones
+/// written by hand there, like the foreword and the author bios.
+#[derive(Clone, Debug)]
+pub struct ConcatOptions {
+    /// The name of the file for all of the prefix chapters. By default that's
+    /// `chapter00.md`, because the only one not skipped is the introduction.
+    pub frontmatter_name: String,
+    /// The name template for a part title page.
+    pub part_name: String,
+    /// The name template for a numbered chapter.
+    pub chapter_name: String,
+    /// The name template for each appendix. Without `{letter}`, all of the
+    /// appendices go in one file instead.
+    pub appendix_name: String,
+    /// The name of the file for all of the suffix chapters.
+    pub backmatter_name: String,
+    /// A numbered chapter whose title starts with this is treated as the
+    /// container for the appendices: each of its sections is an appendix and
+    /// goes in its own file, with the chapter's own page leading the first.
+    pub appendix_title: String,
+    /// Files in the summary to leave out, like the title page and foreword,
+    /// which No Starch lays out themselves.
+    pub skip: Vec<String>,
+    /// Text to put at the top of every file.
+    pub header: String,
+    /// Whether to add a `[TOC]` marker after the header.
+    pub toc: bool,
+}
+
+pub const DEFAULT_HEADER: &str = "\
+<!-- DO NOT EDIT THIS FILE.
+
+This file is periodically generated from the content in the `/src/`
+directory, so all fixes need to be made in `/src/`.
+-->
+";
+
+impl Default for ConcatOptions {
+    fn default() -> Self {
+        ConcatOptions {
+            frontmatter_name: String::from("chapter00.md"),
+            part_name: String::from("part{number}.md"),
+            chapter_name: String::from("chapter{number}.md"),
+            appendix_name: String::from("appendix.md"),
+            backmatter_name: String::from("backmatter.md"),
+            appendix_title: String::from("Appendix"),
+            skip: vec![
+                String::from("title-page.md"),
+                String::from("foreword.md"),
+            ],
+            header: String::from(DEFAULT_HEADER),
+            toc: true,
+        }
+    }
+}
+
+/// The files which are concatenated into one output file.
+#[derive(Debug, PartialEq)]
+pub struct Group {
+    pub name: String,
+    /// A heading to start the file with, for part title pages.
+    pub heading: Option<String>,
+    pub files: Vec<String>,
+}
+
+/// Decide which output file each file in the summary goes in, in the order of
+/// the summary.
+pub fn group_sections(
+    summary: &Summary,
+    options: &ConcatOptions,
+) -> Vec<Group> {
+    let mut groups: Vec<Group> = Vec::new();
+    let mut part_number = 0;
+    let mut frontmatter = None;
+    let mut backmatter = None;
+    let kept = |files: &[String]| -> Vec<String> {
+        files
+            .iter()
+            .filter(|file| !options.skip.contains(file))
+            .cloned()
+            .collect()
+    };
+
+    for entry in &summary.entries {
+        match entry {
+            SummaryEntry::Prefix { file } | SummaryEntry::Suffix { file }
+                if options.skip.contains(file) => {}
+            SummaryEntry::Prefix { file } => push_to_shared_group(
+                &mut groups,
+                &mut frontmatter,
+                &options.frontmatter_name,
+                file,
+            ),
+            SummaryEntry::Suffix { file } => push_to_shared_group(
+                &mut groups,
+                &mut backmatter,
+                &options.backmatter_name,
+                file,
+            ),
+            SummaryEntry::Part { title } => {
+                part_number += 1;
+                groups.push(Group {
+                    name: render_name(&options.part_name, part_number, None),
+                    heading: Some(title.clone()),
+                    files: Vec::new(),
+                });
+            }
+            SummaryEntry::Chapter {
+                number,
+                title,
+                files,
+            } if title.starts_with(&options.appendix_title)
+                && options.appendix_name.contains("{letter}")
+                && files.len() > 1 =>
+            {
+                let files = kept(files);
+                let (intro, appendices) = files.split_at(files.len().min(1));
+                for (index, file) in appendices.iter().enumerate() {
+                    let letter = appendix_letter(index);
+                    let mut files = Vec::new();
+                    if index == 0 {
+                        files.extend(intro.iter().cloned());
+                    }
+                    files.push(file.clone());
+                    groups.push(Group {
+                        name: render_name(
+                            &options.appendix_name,
+                            *number,
+                            Some(&letter),
+                        ),
+                        heading: None,
+                        files,
+                    });
+                }
+            }
+            SummaryEntry::Chapter {
+                number,
+                title,
+                files,
+            } => {
+                let template = if title.starts_with(&options.appendix_title) {
+                    &options.appendix_name
+                } else {
+                    &options.chapter_name
+                };
+                groups.push(Group {
+                    name: render_name(template, *number, None),
+                    heading: None,
+                    files: kept(files),
+                });
+            }
+        }
+    }
+
+    groups
+}
+
+/// Collect all the prefix (or suffix) chapters into one group, wherever the
+/// first of them is.
+fn push_to_shared_group(
+    groups: &mut Vec<Group>,
+    index: &mut Option<usize>,
+    name: &str,
+    file: &str,
+) {
+    let i = *index.get_or_insert_with(|| {
+        groups.push(Group {
+            name: name.to_string(),
+            heading: None,
+            files: Vec::new(),
+        });
+        groups.len() - 1
+    });
+    groups[i].files.push(file.to_string());
+}
+
+fn render_name(template: &str, number: usize, letter: Option<&str>) -> String {
+    let name = template.replace("{number}", &format!("{number:02}"));
+    match letter {
+        Some(letter) => name.replace("{letter}", letter),
+        None => name,
+    }
+}
+
+/// The letter for the appendix at `index`, counting from 0: `a` to `z`, then
+/// `aa` to `zz`, and so on, like spreadsheet columns.
+fn appendix_letter(index: usize) -> String {
+    let mut letters = Vec::new();
+    let mut n = index + 1;
+    while n > 0 {
+        n -= 1;
+        letters.push(char::from(b'a' + (n % 26) as u8));
+        n /= 26;
+    }
+    letters.iter().rev().collect()
+}
+
+/// The result of concatenating the sections of the book.
+#[derive(Debug)]
+pub struct Concatenated {
+    /// The name and contents of each output file, in summary order.
+    pub files: Vec<(String, String)>,
+    /// Any input files which are not in the summary, and so were not included.
+    pub unused: Vec<String>,
+}
+
+/// Concatenate per-section Markdown files into the groups described by
+/// [`group_sections`].
+///
+/// Takes `(file name, contents)` pairs. Every file in the summary must be
+/// present, and must appear in the summary exactly once.
+pub fn concat_chapters<'a>(
+    summary: &Summary,
+    options: &ConcatOptions,
+    sections: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> Result<Concatenated, ConcatError> {
+    let sections: HashMap<&str, &str> = sections.into_iter().collect();
+    let groups = group_sections(summary, options);
+
+    let mut included: BTreeSet<String> = BTreeSet::new();
+    let mut files = Vec::with_capacity(groups.len());
+    for group in groups {
+        let mut contents = options.header.clone();
+        if options.toc {
+            contents.push_str("\n[TOC]\n");
+        }
+        if let Some(heading) = &group.heading {
+            contents.push_str(&format!("\n# {heading}\n"));
+        }
+
+        for file in &group.files {
+            if !included.insert(file.clone()) {
+                return Err(ConcatError::Duplicate { file: file.clone() });
+            }
+            let Some(section) = sections.get(file.as_str()) else {
+                return Err(ConcatError::Missing { file: file.clone() });
+            };
+            contents.push('\n');
+            contents.push_str(section);
+            contents.push('\n');
+        }
+
+        files.push((group.name, contents));
+    }
+
+    let mut unused: Vec<String> = sections
+        .keys()
+        .filter(|name| {
+            !included.contains(**name)
+                && !options.skip.iter().any(|s| s == *name)
+        })
+        .map(|name| name.to_string())
+        .collect();
+    unused.sort();
+
+    Ok(Concatenated { files, unused })
+}
+
+/// Run [`concat_chapters`] on the Markdown files in `source_dir`, writing the
+/// results to `target_dir`.
+pub fn concat_chapter_files(
+    summary: &Summary,
+    options: &ConcatOptions,
+    source_dir: &Path,
+    target_dir: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let mut sections = Vec::new();
+    for entry in read_dir(source_dir)? {
+        let path = entry?.path();
+        if !path.is_file() || path.extension().is_none_or(|ext| ext != "md") {
+            continue;
+        }
+        let name = path
+            .file_name()
+            .expect("file should have a name")
+            .to_string_lossy()
+            .into_owned();
+        sections.push((name, fs::read_to_string(&path)?));
+    }
+
+    let concatenated = concat_chapters(
+        summary,
+        options,
+        sections
+            .iter()
+            .map(|(name, contents)| (name.as_str(), contents.as_str())),
+    )?;
+
+    for name in &concatenated.unused {
+        eprintln!("Not in the summary, skipping: {name}");
+    }
+
+    for (target_name, contents) in concatenated.files {
+        let target_path = target_dir.join(target_name);
+        println!("Concatenating into {}", target_path.to_string_lossy());
+        fs::write(target_path, contents)?;
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum ConcatError {
+    InvalidSummaryLine { line_num: usize, line: String },
+    Duplicate { file: String },
+    Missing { file: String },
+}
+
+impl fmt::Display for ConcatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConcatError::InvalidSummaryLine { line_num, line } => {
+                write!(f, "unexpected line {line_num} in summary: {line}")
+            }
+            ConcatError::Duplicate { file } => {
+                write!(f, "'{file}' appears in the summary more than once")
+            }
+            ConcatError::Missing { file } => {
+                write!(f, "'{file}' is in the summary but was not found")
+            }
+        }
+    }
+}
+
+impl Error for ConcatError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUMMARY: &str = "# The Book
+
+[Title](title-page.md)
+[Foreword](foreword.md)
+
+- [Getting Started](ch01-00-getting-started.md)
+  - [Installation](ch01-01-installation.md)
+    - [Nested](ch01-01-01-nested.md)
+  - [Hello, World!](ch01-02-hello-world.md)
+
+# Part Two
+
+- [Guessing Game](ch02-00-guessing-game-tutorial.md)
+- [Draft]()
+
+---
+
+- [Appendix](appendix-00.md)
+  - [A - Keywords](appendix-01-keywords.md)
+  - [B - Operators](appendix-02-operators.md)
+
+[Contributors](misc/contributors.md)
+";
+
+    fn options() -> ConcatOptions {
+        ConcatOptions {
+            frontmatter_name: String::from("frontmatter.md"),
+            appendix_name: String::from("appendix_{letter}.md"),
+            skip: Vec::new(),
+            header: String::from("HEADER\n"),
+            toc: false,
+            ..ConcatOptions::default()
+        }
+    }
+
+    #[test]
+    fn parses_summary_structure() {
+        let summary = Summary::parse(SUMMARY).unwrap();
+        assert_eq!(
+            summary.entries,
+            vec![
+                SummaryEntry::Prefix {
+                    file: "title-page.md".into()
+                },
+                SummaryEntry::Prefix {
+                    file: "foreword.md".into()
+                },
+                SummaryEntry::Chapter {
+                    number: 1,
+                    title: "Getting Started".into(),
+                    files: vec![
+                        "ch01-00-getting-started.md".into(),
+                        "ch01-01-installation.md".into(),
+                        "ch01-01-01-nested.md".into(),
+                        "ch01-02-hello-world.md".into(),
+                    ],
+                },
+                SummaryEntry::Part {
+                    title: "Part Two".into()
+                },
+                SummaryEntry::Chapter {
+                    number: 2,
+                    title: "Guessing Game".into(),
+                    files: vec!["ch02-00-guessing-game-tutorial.md".into()],
+                },
+                SummaryEntry::Chapter {
+                    number: 3,
+                    title: "Appendix".into(),
+                    files: vec![
+                        "appendix-00.md".into(),
+                        "appendix-01-keywords.md".into(),
+                        "appendix-02-operators.md".into(),
+                    ],
+                },
+                SummaryEntry::Suffix {
+                    file: "contributors.md".into()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_unexpected_summary_lines() {
+        let err = Summary::parse("# Book\n\nSome prose.\n").unwrap_err();
+        assert!(matches!(
+            err,
+            ConcatError::InvalidSummaryLine { line_num: 3, .. }
+        ));
+    }
+
+    #[test]
+    fn groups_follow_the_summary() {
+        let summary = Summary::parse(SUMMARY).unwrap();
+        let names: Vec<_> = group_sections(&summary, &options())
+            .into_iter()
+            .map(|group| group.name)
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "frontmatter.md",
+                "chapter01.md",
+                "part01.md",
+                "chapter02.md",
+                "appendix_a.md",
+                "appendix_b.md",
+                "backmatter.md",
+            ]
+        );
+    }
+
+    #[test]
+    fn appendix_names_do_not_repeat() {
+        let mut summary = String::from("- [Appendix](appendix-00.md)\n");
+        for i in 1..=28 {
+            summary
+                .push_str(&format!("  - [Appendix {i}](appendix-{i:02}.md)\n"));
+        }
+        let summary = Summary::parse(&summary).unwrap();
+        let names: Vec<_> = group_sections(&summary, &options())
+            .into_iter()
+            .map(|group| group.name)
+            .collect();
+
+        assert_eq!(names.len(), 28);
+        assert_eq!(names[25], "appendix_z.md");
+        assert_eq!(names[26], "appendix_aa.md");
+        assert_eq!(names[27], "appendix_ab.md");
+
+        let sections: Vec<_> = (0..=28)
+            .map(|i| (format!("appendix-{i:02}.md"), format!("Appendix {i}")))
+            .collect();
+        let result = concat_chapters(
+            &summary,
+            &options(),
+            sections.iter().map(|(f, c)| (f.as_str(), c.as_str())),
+        )
+        .unwrap();
+        assert_eq!(result.files.len(), 28);
+        assert!(result.files[0].1.contains("Appendix 1"));
+    }
+
+    #[test]
+    fn appendix_letters_count_like_spreadsheet_columns() {
+        let letters: Vec<_> = [0, 1, 25, 26, 27, 51, 52, 701, 702]
+            .map(appendix_letter)
+            .into();
+        assert_eq!(
+            letters,
+            ["a", "b", "z", "aa", "ab", "az", "ba", "zz", "aaa"]
+        );
+    }
+
+    #[test]
+    fn names_are_configurable() {
+        let summary = Summary::parse(SUMMARY).unwrap();
+        let options = ConcatOptions {
+            chapter_name: String::from("ch{number}.md"),
+            appendix_name: String::from("app-{letter}-{number}.md"),
+            ..options()
+        };
+        let names: Vec<_> = group_sections(&summary, &options)
+            .into_iter()
+            .map(|group| group.name)
+            .collect();
+        assert!(names.contains(&String::from("ch01.md")));
+        assert!(names.contains(&String::from("app-b-03.md")));
+    }
+
+    #[test]
+    fn concatenates_in_summary_order_not_file_name_order() {
+        let summary =
+            Summary::parse("- [One](ch01-00-b.md)\n  - [Two](ch01-01-a.md)\n")
+                .unwrap();
+        let result = concat_chapters(
+            &summary,
+            &options(),
+            [("ch01-01-a.md", "Two"), ("ch01-00-b.md", "One")],
+        )
+        .unwrap();
+
+        assert_eq!(
+            result.files,
+            vec![(
+                String::from("chapter01.md"),
+                String::from("HEADER\n\nOne\n\nTwo\n")
+            )]
+        );
+        assert!(result.unused.is_empty());
+    }
+
+    #[test]
+    fn adds_toc_and_part_headings() {
+        let summary = Summary::parse("# Book\n\n# Part One\n").unwrap();
+        let options = ConcatOptions {
+            toc: true,
+            ..options()
+        };
+        let result = concat_chapters(&summary, &options, []).unwrap();
+        assert_eq!(
+            result.files,
+            vec![(
+                String::from("part01.md"),
+                String::from("HEADER\n\n[TOC]\n\n# Part One\n")
+            )]
+        );
+    }
+
+    #[test]
+    fn the_appendix_intro_leads_the_first_appendix() {
+        let summary = Summary::parse(SUMMARY).unwrap();
+        let groups = group_sections(&summary, &options());
+        let first = groups
+            .iter()
+            .find(|group| group.name == "appendix_a.md")
+            .unwrap();
+        assert_eq!(
+            first.files,
+            vec!["appendix-00.md", "appendix-01-keywords.md"]
+        );
+    }
+
+    #[test]
+    fn reports_missing_files() {
+        let summary = Summary::parse("- [One](ch01-00-one.md)\n").unwrap();
+        let err = concat_chapters(&summary, &options(), []).unwrap_err();
+        assert!(
+            matches!(err, ConcatError::Missing { file } if file == "ch01-00-one.md")
+        );
+    }
+
+    #[test]
+    fn reports_duplicate_files() {
+        let summary = Summary::parse(
+            "- [One](ch01-00-one.md)\n- [Again](ch01-00-one.md)\n",
+        )
+        .unwrap();
+        let err =
+            concat_chapters(&summary, &options(), [("ch01-00-one.md", "One")])
+                .unwrap_err();
+        assert!(
+            matches!(err, ConcatError::Duplicate { file } if file == "ch01-00-one.md")
+        );
+    }
+
+    #[test]
+    fn reports_files_not_in_the_summary() {
+        let summary = Summary::parse("- [One](ch01-00-one.md)\n").unwrap();
+        let result = concat_chapters(
+            &summary,
+            &options(),
+            [("ch01-00-one.md", "One"), ("stray.md", "Stray")],
+        )
+        .unwrap();
+        assert_eq!(result.unused, vec![String::from("stray.md")]);
+    }
+
+    #[test]
+    fn skipped_files_are_left_out() {
+        let summary = Summary::parse(
+            "[Title](title-page.md)\n[Foreword](foreword.md)\n\n\
+             - [One](ch01-00-one.md)\n  - [Two](ch01-01-two.md)\n",
+        )
+        .unwrap();
+        let options = ConcatOptions {
+            skip: vec![
+                String::from("title-page.md"),
+                String::from("ch01-01-two.md"),
+            ],
+            ..options()
+        };
+        let result = concat_chapters(
+            &summary,
+            &options,
+            [
+                ("title-page.md", "Title"),
+                ("foreword.md", "Foreword"),
+                ("ch01-00-one.md", "One"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            result.files,
+            vec![
+                (
+                    String::from("frontmatter.md"),
+                    String::from("HEADER\n\nForeword\n")
+                ),
+                (
+                    String::from("chapter01.md"),
+                    String::from("HEADER\n\nOne\n")
+                ),
+            ]
+        );
+        assert!(result.unused.is_empty());
+    }
+
+    #[test]
+    fn appendices_share_a_file_without_a_letter() {
+        let summary = Summary::parse(SUMMARY).unwrap();
+        let options = ConcatOptions {
+            appendix_name: String::from("appendix.md"),
+            ..options()
+        };
+        let groups = group_sections(&summary, &options);
+        let appendix = groups
+            .iter()
+            .find(|group| group.name == "appendix.md")
+            .unwrap();
+        assert_eq!(
+            appendix.files,
+            vec![
+                "appendix-00.md",
+                "appendix-01-keywords.md",
+                "appendix-02-operators.md",
+            ]
+        );
+    }
+
+    /// The defaults must keep producing the files checked in to `nostarch/`,
+    /// and nothing else, or they'd overwrite the files written by hand there.
+    #[test]
+    fn defaults_produce_the_nostarch_files() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+        let text = fs::read_to_string(root.join("src/SUMMARY.md")).unwrap();
+        let summary = Summary::parse(&text).unwrap();
+        let groups = group_sections(&summary, &ConcatOptions::default());
+
+        let names: Vec<_> = groups.iter().map(|g| g.name.clone()).collect();
+        let mut expected: Vec<_> =
+            (0..=21).map(|n| format!("chapter{n:02}.md")).collect();
+        expected.push(String::from("appendix.md"));
+        assert_eq!(names, expected);
+        for name in &names {
+            assert!(root.join("nostarch").join(name).is_file(), "{name}");
+        }
+        assert_eq!(groups[0].files, vec!["ch00-00-introduction.md"]);
+    }
+}
@@ -0,0 +1,355 @@
+//! Convert straight quotes in the prose of a Markdown file to typographic
+//! (“smart”) quotes.
+//!
+//! The input is parsed with `pulldown-cmark` so that only the text of the
+//! prose is touched: code blocks, inline code (however many backticks it uses),
+//! raw HTML, link destinations, and so on all keep their straight quotes. The
+//! rest of the source is passed through byte-for-byte.
+
+use std::ops::Range;
+
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+
+/// A straight quote in the prose which should be replaced.
+#[derive(Debug, PartialEq)]
+pub struct StraightQuote {
+    /// Byte offset of the straight quote in the source.
+    pub offset: usize,
+    /// The straight quote found at `offset`.
+    pub straight: char,
+    /// The smart quote it should become.
+    pub smart: char,
+}
+
+impl StraightQuote {
+    /// Get the 1-based line and column of the quote in the `input` it was
+    /// found in, along with the line itself.
+    pub fn locate<'a>(&self, input: &'a str) -> (usize, usize, &'a str) {
+        let offset = self.offset;
+        let line_start = input[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = input[offset..]
+            .find('\n')
+            .map_or(input.len(), |i| offset + i);
+        let line_num = input[..offset].matches('\n').count() + 1;
+        let col = input[line_start..offset].chars().count() + 1;
+        (line_num, col, &input[line_start..line_end])
+    }
+}
+
+/// Replace every straight quote in the prose of `input` with a smart quote.
+pub fn convert_quotes(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut last = 0;
+    for replacement in find_straight_quotes(input) {
+        output.push_str(&input[last..replacement.offset]);
+        output.push(replacement.smart);
+        last = replacement.offset + replacement.straight.len_utf8();
+    }
+    output.push_str(&input[last..]);
+    output
+}
+
+/// Find every straight quote in the prose of `input`, and which way it should
+/// curl.
+///
+/// This walks the Markdown events, looking only at `Text` outside of code
+/// blocks.
+///
+/// The character *before* a quote is tracked across inline events rather than
+/// taken from the source, so that e.g. the apostrophe in `*Rust*'s` sees the
+/// `t` rather than the `*`. The character *after* a quote is taken from the
+/// source, since the text which follows it may not have been parsed yet.
+pub fn find_straight_quotes(input: &str) -> Vec<StraightQuote> {
+    let mut replacements = Vec::new();
+    let mut code_block_depth = 0;
+    let mut prev: Option<char> = None;
+
+    for (event, range) in Parser::new_ext(input, options()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => code_block_depth += 1,
+            Event::End(TagEnd::CodeBlock) => code_block_depth -= 1,
+
+            Event::Text(_) if code_block_depth > 0 => {}
+            Event::Text(_) => {
+                prev = scan_text(input, range, prev, &mut replacements);
+            }
+
+            // Inline code reads as a word for the purposes of deciding which
+            // way a following quote should curl.
+            Event::Code(_) | Event::FootnoteReference(_) => {
+                prev = input[range].chars().last();
+            }
+
+            Event::SoftBreak | Event::HardBreak => prev = Some('\n'),
+
+            // Inline markup like emphasis, links, or inline HTML tags is
+            // transparent…
+            Event::InlineHtml(_) => {}
+            Event::Start(
+                Tag::Emphasis
+                | Tag::Strong
+                | Tag::Strikethrough
+                | Tag::Link { .. }
+                | Tag::Image { .. },
+            )
+            | Event::End(
+                TagEnd::Emphasis
+                | TagEnd::Strong
+                | TagEnd::Strikethrough
+                | TagEnd::Link
+                | TagEnd::Image,
+            ) => {}
+
+            // …but every other boundary starts over, like a new paragraph.
+            _ => prev = None,
+        }
+    }
+
+    replacements
+}
+
+/// Find the straight quotes in the source for one `Text` event, returning the
+/// last character of the text so the next event can pick up where it left off.
+fn scan_text(
+    input: &str,
+    range: Range<usize>,
+    mut prev: Option<char>,
+    replacements: &mut Vec<StraightQuote>,
+) -> Option<char> {
+    for (index, c) in input[range.clone()].char_indices() {
+        let offset = range.start + index;
+        // An escaped quote is a deliberate straight quote.
+        if (c == '\'' || c == '"') && !is_escaped(input, offset) {
+            let next = input[offset + 1..].chars().next();
+            let smart = if c == '\'' {
+                single_quote(prev, next)
+            } else {
+                double_quote(prev, next)
+            };
+            replacements.push(StraightQuote {
+                offset,
+                straight: c,
+                smart,
+            });
+            prev = Some(smart);
+        } else {
+            prev = Some(c);
+        }
+    }
+    prev
+}
+
+/// Whether the character at `offset` is preceded by an odd number of
+/// backslashes. (`pulldown-cmark` does not include the backslash itself in the
+/// `Text` event for an escape, so this has to look at the source.)
+fn is_escaped(input: &str, offset: usize) -> bool {
+    let backslashes = input[..offset]
+        .bytes()
+        .rev()
+        .take_while(|&b| b == b'\\')
+        .count();
+    backslashes % 2 == 1
+}
+
+/// Whether the character before a quote means the quote is opening something.
+fn is_opening_context(prev: Option<char>) -> bool {
+    match prev {
+        None => true,
+        Some(c) => {
+            c.is_whitespace()
+                || matches!(c, '(' | '[' | '{' | '“' | '‘' | '—' | '–' | '/')
+        }
+    }
+}
+
+fn single_quote(prev: Option<char>, next: Option<char>) -> char {
+    if !is_opening_context(prev) {
+        // Closing quote, contraction, or possessive: `it's`, `Carol's`,
+        // `the crates'`, `'hi.'`.
+        return '’';
+    }
+
+    match next {
+        // Nothing follows, so there is nothing to open.
+        None => '’',
+        Some(c) if c.is_whitespace() => '’',
+        // Elided digits, like `'90s`.
+        Some(c) if c.is_ascii_digit() => '’',
+        Some(_) => '‘',
+    }
+}
+
+fn double_quote(prev: Option<char>, next: Option<char>) -> char {
+    if is_opening_context(prev) && next.is_some_and(|c| !c.is_whitespace()) {
+        '“'
+    } else {
+        '”'
+    }
+}
+
+/// Matches the options used to render the book, except that smart punctuation
+/// is left *off*: we want to see the straight quotes in the source.
+fn options() -> Options {
+    let mut opts = Options::empty();
+    opts.insert(Options::ENABLE_TABLES);
+    opts.insert(Options::ENABLE_FOOTNOTES);
+    opts.insert(Options::ENABLE_STRIKETHROUGH);
+    opts.insert(Options::ENABLE_TASKLISTS);
+    opts.insert(Options::ENABLE_HEADING_ATTRIBUTES);
+    opts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{convert_quotes, find_straight_quotes};
+
+    #[test]
+    fn converts_double_quotes() {
+        assert_eq!(
+            convert_quotes(r#"She said "hello" to me."#),
+            "She said “hello” to me."
+        );
+    }
+
+    #[test]
+    fn converts_apostrophes() {
+        assert_eq!(
+            convert_quotes("It's Carol's book, not the authors' book."),
+            "It’s Carol’s book, not the authors’ book."
+        );
+    }
+
+    #[test]
+    fn converts_nested_quotes() {
+        assert_eq!(
+            convert_quotes(r#""She said 'hi' to me," he said."#),
+            "“She said ‘hi’ to me,” he said."
+        );
+        assert_eq!(
+            convert_quotes(r#"He wrote "a 'season.'""#),
+            "He wrote “a ‘season.’”"
+        );
+    }
+
+    #[test]
+    fn converts_quotes_at_start_of_paragraph() {
+        assert_eq!(convert_quotes(r#""Yes," I said."#), "“Yes,” I said.");
+        assert_eq!(convert_quotes("'Yes,' I said."), "‘Yes,’ I said.");
+    }
+
+    #[test]
+    fn converts_elided_digits() {
+        assert_eq!(convert_quotes("back in the '90s"), "back in the ’90s");
+    }
+
+    #[test]
+    fn converts_apostrophes_after_closing_punctuation() {
+        assert_eq!(convert_quotes("*Rust*'s syntax"), "*Rust*’s syntax");
+        assert_eq!(convert_quotes("`Vec<T>`'s methods"), "`Vec<T>`’s methods");
+        assert_eq!(
+            convert_quotes("[the book](https://example.com)'s index"),
+            "[the book](https://example.com)’s index"
+        );
+        assert_eq!(
+            convert_quotes(r#"(see "Methods")'s notes"#),
+            "(see “Methods”)’s notes"
+        );
+    }
+
+    #[test]
+    fn converts_quotes_around_inline_markup() {
+        assert_eq!(convert_quotes(r#"the "*right*" way"#), "the “*right*” way");
+    }
+
+    #[test]
+    fn leaves_code_blocks_alone() {
+        let input = r#"Don't touch:
+
+```rust
+let c = 'c';
+let s = "it's";
+```
+
+    let indented = "code";
+
+But "do" touch this.
+"#;
+        let expected = r#"Don’t touch:
+
+```rust
+let c = 'c';
+let s = "it's";
+```
+
+    let indented = "code";
+
+But “do” touch this.
+"#;
+        assert_eq!(convert_quotes(input), expected);
+    }
+
+    #[test]
+    fn leaves_inline_code_alone() {
+        assert_eq!(
+            convert_quotes(r#"Use `'a'` and `"a"`, don't."#),
+            r#"Use `'a'` and `"a"`, don’t."#
+        );
+    }
+
+    #[test]
+    fn leaves_double_backtick_code_alone() {
+        assert_eq!(
+            convert_quotes(r#"Write ``let s = "`";`` and ``'`'``, it's fine."#),
+            r#"Write ``let s = "`";`` and ``'`'``, it’s fine."#
+        );
+    }
+
+    #[test]
+    fn inline_code_does_not_leak_across_paragraphs() {
+        let input = "An unclosed ` backtick.\n\nIt's still prose.";
+        let expected = "An unclosed ` backtick.\n\nIt’s still prose.";
+        assert_eq!(convert_quotes(input), expected);
+    }
+
+    #[test]
+    fn leaves_html_alone() {
+        let input = r#"<span class="filename">Filename: src/main.rs</span>
+
+A <a href="x" title='y'>"link"</a> here."#;
+        let expected = r#"<span class="filename">Filename: src/main.rs</span>
+
+A <a href="x" title='y'>“link”</a> here."#;
+        assert_eq!(convert_quotes(input), expected);
+    }
+
+    #[test]
+    fn leaves_link_destinations_and_titles_alone() {
+        let input = r#"A ["quoted"](https://example.com/it's "It's") link."#;
+        let expected = r#"A [“quoted”](https://example.com/it's "It's") link."#;
+        assert_eq!(convert_quotes(input), expected);
+    }
+
+    #[test]
+    fn leaves_escaped_quotes_alone() {
+        assert_eq!(
+            convert_quotes(r#"A literal \" and \' here."#),
+            r#"A literal \" and \' here."#
+        );
+    }
+
+    #[test]
+    fn leaves_smart_quotes_alone() {
+        let input = "“Already” ‘smart’ isn’t changed.";
+        assert_eq!(convert_quotes(input), input);
+    }
+
+    #[test]
+    fn finds_only_quotes_in_prose() {
+        let input = "Plain `'code'`\n\nand \"prose\"\n";
+        let found = find_straight_quotes(input);
+        assert_eq!(found.len(), 2);
+
+        let (line, col, text) = found[0].locate(input);
+        assert_eq!((line, col, text), (3, 5, "and \"prose\""));
+    }
+}
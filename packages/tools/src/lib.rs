@@ -0,0 +1,34 @@
+//! Support tooling for maintaining _The Rust Programming Language_.
+//!
+//! Each of the text transformations here is also available as a binary of the
+//! same name, which reads Markdown from stdin and writes the result to stdout.
+
+pub mod cli;
+
+mod cleanup_blockquotes;
+mod concat_chapters;
+mod convert_quotes;
+mod link2print;
+pub mod lint;
+pub mod manifest;
+pub mod megadiff;
+pub mod nostarch;
+pub mod output;
+pub mod release;
+mod remove_hidden_lines;
+mod remove_links;
+mod remove_markup;
+pub mod snippets;
+#[cfg(test)]
+mod test_util;
+
+pub use cleanup_blockquotes::cleanup_blockquotes;
+pub use concat_chapters::{
+    ConcatError, ConcatOptions, Concatenated, DEFAULT_HEADER, Group, Summary,
+    SummaryEntry, concat_chapter_files, concat_chapters, group_sections,
+};
+pub use convert_quotes::{StraightQuote, convert_quotes, find_straight_quotes};
+pub use link2print::link2print;
+pub use remove_hidden_lines::remove_hidden_lines;
+pub use remove_links::remove_links;
+pub use remove_markup::remove_markup;
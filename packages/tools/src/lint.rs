@@ -0,0 +1,645 @@
+//! A small rule-based linter for the book's Markdown and for the `output.txt`
+//! files included from the listings.
+//!
+//! Each [`Rule`] has a default [`Level`], which a [`Config`] can override. Only
+//! findings from rules at [`Level::Deny`] should fail a check.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+/// The kinds of files the rules know how to check.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileKind {
+    /// Book text, e.g. `src/ch01-00-getting-started.md`.
+    Markdown,
+    /// Command output shown in the book, e.g. `listings/**/output.txt`.
+    Output,
+}
+
+impl FileKind {
+    /// Which kind of file `path` is, if it is one we lint at all.
+    pub fn of(path: &Path) -> Option<FileKind> {
+        if path.file_name().is_some_and(|name| name == "output.txt") {
+            Some(FileKind::Output)
+        } else if path.extension().is_some_and(|ext| ext == "md") {
+            Some(FileKind::Markdown)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Named after the `--allow`, `--warn` and `--deny` flags, and padded
+        // so `lfp --list` can line them up in a column.
+        f.pad(match self {
+            Level::Allow => "allow",
+            Level::Warn => "warn",
+            Level::Deny => "deny",
+        })
+    }
+}
+
+/// One line of a file which a rule objects to.
+#[derive(Debug, PartialEq)]
+pub struct Finding {
+    /// 1-based line number.
+    pub line_num: usize,
+    pub message: String,
+}
+
+pub struct Rule {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub default_level: Level,
+    kinds: &'static [FileKind],
+    check: fn(&[Line<'_>]) -> Vec<Finding>,
+}
+
+impl Rule {
+    pub fn applies_to(&self, kind: FileKind) -> bool {
+        self.kinds.contains(&kind)
+    }
+}
+
+/// A line of a file, along with whether it is inside a fenced code block (which
+/// is only ever true for Markdown files).
+pub struct Line<'a> {
+    pub num: usize,
+    pub text: &'a str,
+    pub in_code_block: bool,
+}
+
+const ALL_KINDS: &[FileKind] = &[FileKind::Markdown, FileKind::Output];
+
+pub static RULES: &[Rule] = &[
+    Rule {
+        name: "file-url",
+        description: "`file://` URLs other than the normalized `file:///projects/…` and `file:///home/.cargo` or `file:///home/.rustup`",
+        default_level: Level::Deny,
+        kinds: ALL_KINDS,
+        check: check_file_urls,
+    },
+    Rule {
+        name: "home-dir",
+        description: "absolute paths into someone's home directory, like `/home/me` or `/Users/me`",
+        default_level: Level::Deny,
+        kinds: ALL_KINDS,
+        check: check_home_dirs,
+    },
+    Rule {
+        name: "machine-path",
+        description: "paths which only exist on one machine, like toolchains with a host triple or temporary directories",
+        default_level: Level::Deny,
+        kinds: ALL_KINDS,
+        check: check_machine_paths,
+    },
+    Rule {
+        name: "compile-time",
+        description: "compile times in a format the output regeneration would not have produced",
+        default_level: Level::Deny,
+        kinds: &[FileKind::Output],
+        check: check_compile_times,
+    },
+    Rule {
+        name: "test-hash",
+        description: "test binary hashes which are malformed or differ within one output file",
+        default_level: Level::Deny,
+        kinds: &[FileKind::Output],
+        check: check_test_hashes,
+    },
+    Rule {
+        name: "trailing-whitespace",
+        description: "whitespace at the end of a line of prose",
+        default_level: Level::Warn,
+        kinds: &[FileKind::Markdown],
+        check: check_trailing_whitespace,
+    },
+    Rule {
+        name: "double-space",
+        description: "two or more spaces between words in prose",
+        default_level: Level::Warn,
+        kinds: &[FileKind::Markdown],
+        check: check_double_spaces,
+    },
+];
+
+pub fn rule(name: &str) -> Option<&'static Rule> {
+    RULES.iter().find(|rule| rule.name == name)
+}
+
+/// The level of each rule.
+#[derive(Clone, Debug)]
+pub struct Config {
+    levels: BTreeMap<&'static str, Level>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            levels: RULES
+                .iter()
+                .map(|rule| (rule.name, rule.default_level))
+                .collect(),
+        }
+    }
+}
+
+impl Config {
+    pub fn set(&mut self, name: &str, level: Level) -> Result<(), String> {
+        let rule = rule(name).ok_or_else(|| {
+            let known: Vec<_> = RULES.iter().map(|rule| rule.name).collect();
+            format!("unknown rule '{name}'; known rules: {}", known.join(", "))
+        })?;
+        self.levels.insert(rule.name, level);
+        Ok(())
+    }
+
+    pub fn level(&self, name: &str) -> Level {
+        self.levels.get(name).copied().unwrap_or(Level::Allow)
+    }
+}
+
+/// A finding from a rule which is not allowed.
+#[derive(Debug, PartialEq)]
+pub struct Violation {
+    pub rule: &'static str,
+    pub level: Level,
+    pub finding: Finding,
+}
+
+/// Run every rule which applies to `kind` and is not allowed by `config`.
+pub fn lint(text: &str, kind: FileKind, config: &Config) -> Vec<Violation> {
+    let lines = split_lines(text, kind);
+    let mut violations: Vec<Violation> = RULES
+        .iter()
+        .filter(|rule| rule.applies_to(kind))
+        .filter(|rule| config.level(rule.name) != Level::Allow)
+        .flat_map(|rule| {
+            (rule.check)(&lines).into_iter().map(|finding| Violation {
+                rule: rule.name,
+                level: config.level(rule.name),
+                finding,
+            })
+        })
+        .collect();
+    violations.sort_by_key(|violation| violation.finding.line_num);
+    violations
+}
+
+fn split_lines(text: &str, kind: FileKind) -> Vec<Line<'_>> {
+    let mut in_code_block = false;
+    text.lines()
+        .enumerate()
+        .map(|(index, text)| {
+            let content = strip_blockquote(text);
+            let is_fence = kind == FileKind::Markdown
+                && (content.starts_with("```") || content.starts_with("~~~"));
+            // The fences themselves count as part of the code block.
+            let line = Line {
+                num: index + 1,
+                text,
+                in_code_block: in_code_block || is_fence,
+            };
+            if is_fence {
+                in_code_block = !in_code_block;
+            }
+            line
+        })
+        .collect()
+}
+
+/// Strip any block quote markers and indentation from the start of a line.
+fn strip_blockquote(text: &str) -> &str {
+    let mut text = text.trim_start();
+    while let Some(rest) = text.strip_prefix('>') {
+        text = rest.trim_start();
+    }
+    text
+}
+
+/// Whether a line is indented enough inside any block quotes to be an indented
+/// code block (or the continuation of a list item, which is too hard to tell
+/// apart without parsing the whole file).
+fn is_indented(text: &str) -> bool {
+    let mut text = text;
+    while let Some(rest) = text.trim_start_matches(' ').strip_prefix('>') {
+        text = rest.strip_prefix(' ').unwrap_or(rest);
+    }
+    text.starts_with("    ") || text.starts_with('\t')
+}
+
+fn findings<'a>(
+    lines: &'a [Line<'a>],
+    mut check: impl FnMut(&Line<'a>) -> Option<String>,
+) -> Vec<Finding> {
+    lines
+        .iter()
+        .filter_map(|line| {
+            check(line).map(|message| Finding {
+                line_num: line.num,
+                message,
+            })
+        })
+        .collect()
+}
+
+fn check_file_urls(lines: &[Line<'_>]) -> Vec<Finding> {
+    findings(lines, |line| {
+        line.text
+            .split_whitespace()
+            .find(|sub_string| {
+                sub_string.contains("file://")
+                    && !sub_string.contains("file:///projects/")
+                    && !sub_string.contains("file:///home/.cargo")
+                    && !sub_string.contains("file:///home/.rustup")
+            })
+            .map(|url| format!("machine-specific file URL: {url}"))
+    })
+}
+
+lazy_static! {
+    static ref HOME_DIR: Regex =
+        Regex::new(r"(?:/home/|/Users/|[A-Za-z]:\\Users\\)([^/\\\s`)]+)")
+            .unwrap();
+    static ref MACHINE_PATH: Regex = Regex::new(
+        r"(?x)
+        toolchains/[^/\s]*-(?:unknown-linux-gnu|apple-darwin|pc-windows-(?:msvc|gnu))
+        |
+        (?:^|[\s(`'\x22])(?:/tmp/|/private/var/|/var/folders/)\S*
+        "
+    )
+    .unwrap();
+    static ref COMPILE_TIME: Regex =
+        Regex::new(r"Finished .* target\(s\) in (\S+)").unwrap();
+    static ref NORMALIZED_COMPILE_TIME: Regex =
+        Regex::new(r"^\d+\.\d{1,2}s$").unwrap();
+    static ref TEST_BINARY: Regex = Regex::new(
+        r"Running [^\s]+(?: [^\s()]+)? \((target/(?:[^/\s]+/)*deps/)[^\s)]*-([^-\s)]+)\)"
+    )
+    .unwrap();
+    static ref NORMALIZED_TEST_HASH: Regex =
+        Regex::new(r"^[0-9a-f]{16}$").unwrap();
+}
+
+fn check_home_dirs(lines: &[Line<'_>]) -> Vec<Finding> {
+    findings(lines, |line| {
+        HOME_DIR
+            .captures_iter(line.text)
+            // The normalized paths are `file:///home/.cargo` and friends.
+            .find(|caps| !caps[1].starts_with('.'))
+            .map(|caps| format!("absolute home directory: {}", &caps[0]))
+    })
+}
+
+fn check_machine_paths(lines: &[Line<'_>]) -> Vec<Finding> {
+    findings(lines, |line| {
+        MACHINE_PATH.find(line.text).map(|found| {
+            format!("machine-specific path: {}", found.as_str().trim_start())
+        })
+    })
+}
+
+fn check_compile_times(lines: &[Line<'_>]) -> Vec<Finding> {
+    findings(lines, |line| {
+        let caps = COMPILE_TIME.captures(line.text)?;
+        let time = &caps[1];
+        (!NORMALIZED_COMPILE_TIME.is_match(time))
+            .then(|| format!("compile time `{time}` is not like `0.42s`"))
+    })
+}
+
+fn check_test_hashes(lines: &[Line<'_>]) -> Vec<Finding> {
+    let mut first_hash: Option<String> = None;
+    findings(lines, |line| {
+        let caps = TEST_BINARY.captures(line.text)?;
+        let (dir, hash) = (&caps[1], &caps[2]);
+        if dir != "target/debug/deps/" {
+            return Some(format!(
+                "test binary is not in `target/debug/deps/`: {dir}"
+            ));
+        }
+        if !NORMALIZED_TEST_HASH.is_match(hash) {
+            return Some(format!("malformed test binary hash `{hash}`"));
+        }
+        // Regenerating the output reuses the first hash for every binary.
+        let first = first_hash.get_or_insert_with(|| hash.to_string());
+        (first != hash).then(|| {
+            format!(
+                "test binary hash `{hash}` differs from the first, `{first}`"
+            )
+        })
+    })
+}
+
+fn check_trailing_whitespace(lines: &[Line<'_>]) -> Vec<Finding> {
+    findings(lines, |line| {
+        (!line.in_code_block && line.text.ends_with([' ', '\t']))
+            .then(|| String::from("trailing whitespace"))
+    })
+}
+
+fn check_double_spaces(lines: &[Line<'_>]) -> Vec<Finding> {
+    findings(lines, |line| {
+        if line.in_code_block {
+            return None;
+        }
+        // Leading indentation is structure, not spacing between words, and
+        // indented lines are likely code; trailing spaces are covered by
+        // `trailing-whitespace`; tables are often aligned with extra spaces.
+        if is_indented(line.text) {
+            return None;
+        }
+        let prose = strip_blockquote(line.text).trim_end();
+        if prose.starts_with('|') {
+            return None;
+        }
+        let mut in_code = false;
+        let mut previous = None;
+        for c in prose.chars() {
+            if c == '`' {
+                in_code = !in_code;
+            }
+            if !in_code && c == ' ' && previous == Some(' ') {
+                return Some(String::from("double space"));
+            }
+            previous = Some(c);
+        }
+        None
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn rule_findings(name: &str, kind: FileKind, text: &str) -> Vec<usize> {
+        let rule = rule(name).unwrap();
+        assert!(rule.applies_to(kind));
+        (rule.check)(&split_lines(text, kind))
+            .into_iter()
+            .map(|finding| finding.line_num)
+            .collect()
+    }
+
+    #[test]
+    fn lint_file_returns_a_vec_with_errs_when_lines_of_interest_are_found() {
+        let string = r#"
+        $ cargo run
+               Compiling guessing_game v0.1.0 (file:///home/you/projects/guessing_game)
+                 Running `target/guessing_game`
+            Guess the number!
+            The secret number is: 61
+            Please input your guess.
+            10
+            You guessed: 10
+            Too small!
+            Please input your guess.
+            99
+            You guessed: 99
+            Too big!
+            Please input your guess.
+            foo
+            Please input your guess.
+            61
+            You guessed: 61
+            You win!
+            $ cargo run
+               Compiling guessing_game v0.1.0 (file:///home/you/projects/guessing_game)
+                 Running `target/debug/guessing_game`
+            Guess the number!
+            The secret number is: 7
+            Please input your guess.
+            4
+            You guessed: 4
+            $ cargo run
+                 Running `target/debug/guessing_game`
+            Guess the number!
+            The secret number is: 83
+            Please input your guess.
+            5
+            $ cargo run
+               Compiling guessing_game v0.1.0 (file:///home/you/projects/guessing_game)
+                 Running `target/debug/guessing_game`
+            Hello, world!
+        "#;
+
+        let result_vec = rule_findings("file-url", FileKind::Markdown, string);
+
+        assert!(!result_vec.is_empty());
+        assert_eq!(3, result_vec.len());
+    }
+
+    #[test]
+    fn lint_file_returns_an_empty_vec_when_no_lines_of_interest_are_found() {
+        let string = r#"
+            $ cargo run
+               Compiling guessing_game v0.1.0 (file:///projects/guessing_game)
+                 Running `target/guessing_game`
+            Guess the number!
+            The secret number is: 61
+            Please input your guess.
+            10
+            You guessed: 10
+            Too small!
+            Please input your guess.
+            99
+            You guessed: 99
+            Too big!
+            Please input your guess.
+            foo
+            Please input your guess.
+            61
+            You guessed: 61
+            You win!
+        "#;
+
+        let result_vec = rule_findings("file-url", FileKind::Markdown, string);
+
+        assert!(result_vec.is_empty());
+    }
+
+    #[test]
+    fn file_kind_is_none_when_the_path_is_a_directory() {
+        assert_eq!(FileKind::of(Path::new("src/img")), None);
+    }
+
+    #[test]
+    fn file_kind_is_none_when_the_filename_does_not_have_the_md_extension() {
+        assert_eq!(FileKind::of(Path::new("src/img/foo1.png")), None);
+    }
+
+    #[test]
+    fn file_kind_is_markdown_when_the_filename_has_the_md_extension() {
+        assert_eq!(
+            FileKind::of(Path::new("src/ch01-00-introduction.md")),
+            Some(FileKind::Markdown)
+        );
+    }
+
+    #[test]
+    fn file_kind_is_output_for_output_txt() {
+        assert_eq!(
+            FileKind::of(Path::new("listings/ch02/listing-02-01/output.txt")),
+            Some(FileKind::Output)
+        );
+        assert_eq!(FileKind::of(Path::new("listings/ch02/notes.txt")), None);
+    }
+
+    #[test]
+    fn file_url_does_not_report_a_line_if_the_line_contains_a_file_url_which_is_directly_followed_by_the_project_path()
+     {
+        let sample_line =
+            "Compiling guessing_game v0.1.0 (file:///projects/guessing_game)";
+
+        assert!(
+            rule_findings("file-url", FileKind::Output, sample_line).is_empty()
+        );
+    }
+
+    #[test]
+    fn file_url_reports_a_line_if_the_line_contains_a_file_url_which_is_not_directly_followed_by_the_project_path()
+     {
+        let sample_line = "Compiling guessing_game v0.1.0 (file:///home/you/projects/guessing_game)";
+
+        assert_eq!(
+            rule_findings("file-url", FileKind::Output, sample_line),
+            vec![1]
+        );
+    }
+
+    #[test]
+    fn home_dir_reports_home_directories_but_not_normalized_ones() {
+        let text = "\
+ --> /Users/carol/projects/hello/src/main.rs:2:5
+  --> file:///home/.rustup/toolchains/1.85/lib/rustlib/src/rust/library/core/src/option.rs:1:1
+error: could not read /home/steve/book/src/main.rs
+C:\\Users\\chris\\projects\\hello";
+        assert_eq!(
+            rule_findings("home-dir", FileKind::Output, text),
+            vec![1, 3, 4]
+        );
+    }
+
+    #[test]
+    fn machine_path_reports_toolchain_triples_and_temp_dirs() {
+        let text = "\
+  --> file:///home/.rustup/toolchains/1.85/lib/rustlib/src/rust/library/core/src/option.rs:1:1
+  --> /rustup/toolchains/1.85-aarch64-apple-darwin/lib/rustlib/src/rust/library/core/src/option.rs
+Compiling hello v0.1.0 (/tmp/hello)
+Install for `x86_64-unknown-linux-gnu` targets";
+        assert_eq!(
+            rule_findings("machine-path", FileKind::Output, text),
+            vec![2, 3]
+        );
+    }
+
+    #[test]
+    fn compile_time_reports_unusual_formats() {
+        let text = "\
+    Finished `dev` profile [unoptimized + debuginfo] target(s) in 0.42s
+    Finished `test` profile [unoptimized + debuginfo] target(s) in 1m 02s
+    Finished `dev` profile [unoptimized + debuginfo] target(s) in 12.345s";
+        assert_eq!(
+            rule_findings("compile-time", FileKind::Output, text),
+            vec![2, 3]
+        );
+    }
+
+    #[test]
+    fn test_hash_reports_inconsistent_and_malformed_hashes() {
+        let text = "\
+     Running unittests src/lib.rs (target/debug/deps/adder-92948b65e88960b4)
+     Running tests/integration_test.rs (target/debug/deps/integration_test-92948b65e88960b4)
+     Running tests/other.rs (target/debug/deps/other-1082c4b063a8fbe6)
+     Running unittests src/lib.rs (target/debug/deps/adder-92948b65e88960b4.exe)
+     Running unittests src/lib.rs (target/x86_64-unknown-linux-gnu/debug/deps/adder-92948b65e88960b4)";
+        assert_eq!(
+            rule_findings("test-hash", FileKind::Output, text),
+            vec![3, 4, 5]
+        );
+    }
+
+    #[test]
+    fn trailing_whitespace_ignores_code_blocks() {
+        let text = "Prose. \n\n```text\ncode \n```\nMore prose.\t";
+        assert_eq!(
+            rule_findings("trailing-whitespace", FileKind::Markdown, text),
+            vec![1, 6]
+        );
+    }
+
+    #[test]
+    fn double_space_ignores_code_indentation_and_tables() {
+        let text = "\
+Two  spaces.
+    indented code
+- list item with `code  spacing`
+| a  | b |
+
+```
+let  x = 1;
+```
+>     let  quoted = 1;
+> ```
+> let  fenced = 1;
+> ```
+> Quoted  prose.
+One space.";
+        assert_eq!(
+            rule_findings("double-space", FileKind::Markdown, text),
+            vec![1, 13]
+        );
+    }
+
+    #[test]
+    fn rules_only_apply_to_their_kinds_of_files() {
+        let text = "    Finished `dev` profile [unoptimized + debuginfo] target(s) in 1m 02s  ";
+        let output = lint(text, FileKind::Output, &Config::default());
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0].rule, "compile-time");
+
+        let markdown = lint(text, FileKind::Markdown, &Config::default());
+        let rules: Vec<_> = markdown.iter().map(|v| v.rule).collect();
+        assert_eq!(rules, vec!["trailing-whitespace"]);
+    }
+
+    #[test]
+    fn config_changes_levels() {
+        let text = "Trailing. ";
+        let mut config = Config::default();
+
+        let violations = lint(text, FileKind::Markdown, &config);
+        assert_eq!(violations[0].level, Level::Warn);
+
+        config.set("trailing-whitespace", Level::Deny).unwrap();
+        let violations = lint(text, FileKind::Markdown, &config);
+        assert_eq!(violations[0].level, Level::Deny);
+
+        config.set("trailing-whitespace", Level::Allow).unwrap();
+        assert!(lint(text, FileKind::Markdown, &config).is_empty());
+
+        assert!(config.set("no-such-rule", Level::Deny).is_err());
+    }
+
+    #[test]
+    fn levels_are_named_like_their_flags_and_padded() {
+        assert_eq!(format!("{:<6}|", Level::Warn), "warn  |");
+        assert_eq!(format!("{}", Level::Deny), "deny");
+        assert_eq!(format!("{:>5}", Level::Allow), "allow");
+    }
+}
@@ -0,0 +1,423 @@
+//! The `listing.toml` file describing how to build a listing and what should
+//! happen when we do.
+//!
+//! Every field is optional, and a listing without a `listing.toml` gets the
+//! defaults: the commands come from the `$ command` lines of its `output.txt`,
+//! if it has any, their outcomes are not checked, and it is formatted with
+//! rustfmt.
+//!
+//! ```toml
+//! command = "cargo run"
+//! outcome = "panics"
+//! stdin = "42\n"
+//! # rustfmt can't handle the comments pointing out scopes in this listing.
+//! rustfmt = false
+//!
+//! [env]
+//! RUST_LOG = "debug"
+//! ```
+//!
+//! Interactive programs can be given their input a line at a time, each one
+//! typed after the program prints its prompt, and transcripts can show the
+//! same program being run more than once. Settings at the top level apply to
+//! every run which doesn't have its own.
+//!
+//! ```toml
+//! # Build the dependencies first so the output only shows the game compiling.
+//! setup = ["cargo build", "touch src/main.rs"]
+//! prompt = "Please input your guess."
+//! # Keep what the game printed for the secret number it picked last time.
+//! preserve = "The secret number is: (\\d+)"
+//!
+//! [[run]]
+//! stdin = "4\n"
+//!
+//! [[run]]
+//! stdin = "5\n"
+//! ```
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use serde::Deserialize;
+
+/// The name of the manifest file in a listing's directory.
+pub const FILE_NAME: &str = "listing.toml";
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    /// The command whose output the book shows, like `cargo test`. This takes
+    /// precedence over the `$ command` line in `output.txt`.
+    pub command: Option<String>,
+    /// What should happen when running the command.
+    pub outcome: Option<Outcome>,
+    /// Input to type into the program, for interactive ones.
+    pub stdin: Option<String>,
+    /// What the program prints when it is waiting for input. If this is set,
+    /// each line of `stdin` is typed after the next time the program prints
+    /// it, and shows up in the output as it would in a terminal.
+    pub prompt: Option<String>,
+    /// A regex matching a value which is different every time the program
+    /// runs, like a random number. What the program printed is kept from the
+    /// previous output, as long as it still prints the same up to the match,
+    /// apart from the first group (or the whole match, if there are no
+    /// groups). Programs which can take a seed for their randomness should get
+    /// one through `env` instead.
+    pub preserve: Option<String>,
+    /// Commands to run before the ones whose output is shown, like building
+    /// the dependencies so the output doesn't show them being compiled.
+    #[serde(default)]
+    pub setup: Vec<String>,
+    /// Each command the output shows, in order, if it shows more than one or
+    /// they need different settings.
+    #[serde(default)]
+    pub run: Vec<Run>,
+    /// Environment variables to set when running the command.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Whether `cargo fmt` should be run on the listing. Some listings
+    /// deliberately don't parse, or have comments rustfmt would mangle.
+    #[serde(default = "default_rustfmt")]
+    pub rustfmt: bool,
+}
+
+fn default_rustfmt() -> bool {
+    true
+}
+
+impl Default for Manifest {
+    fn default() -> Self {
+        Manifest {
+            command: None,
+            outcome: None,
+            stdin: None,
+            prompt: None,
+            preserve: None,
+            setup: Vec::new(),
+            run: Vec::new(),
+            env: BTreeMap::new(),
+            rustfmt: default_rustfmt(),
+        }
+    }
+}
+
+impl Manifest {
+    pub fn parse(text: &str) -> Result<Manifest, toml::de::Error> {
+        toml::from_str(text)
+    }
+
+    /// Load the manifest in `listing_dir`, or the defaults if there isn't one.
+    pub fn load(listing_dir: &Path) -> Result<Manifest, Box<dyn Error>> {
+        let path = listing_dir.join(FILE_NAME);
+        match fs::read_to_string(&path) {
+            Ok(text) => Manifest::parse(&text).map_err(|e| {
+                format!("Invalid manifest '{}': {e}", path.display()).into()
+            }),
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                Ok(Manifest::default())
+            }
+            Err(e) => {
+                Err(format!("Could not read '{}': {e}", path.display()).into())
+            }
+        }
+    }
+
+    /// The commands to run to produce a listing's output, given the ones
+    /// `shown` on the `$ command` lines of its current output.
+    ///
+    /// Without any `[[run]]` tables, that is the manifest's `command` or else
+    /// each of the `shown` ones. With them, it is one command per table, which
+    /// defaults to the manifest's `command` and then to the `shown` command in
+    /// the same position.
+    pub fn steps(&self, shown: &[&str]) -> Result<Vec<Step>, Box<dyn Error>> {
+        let runs = if !self.run.is_empty() {
+            self.run.clone()
+        } else if self.command.is_some() || shown.is_empty() {
+            vec![Run::default()]
+        } else {
+            vec![Run::default(); shown.len()]
+        };
+
+        runs.into_iter()
+            .enumerate()
+            .map(|(i, run)| {
+                let command = run
+                    .command
+                    .or_else(|| self.command.clone())
+                    .or_else(|| shown.get(i).map(|command| command.to_string()))
+                    .ok_or_else(|| {
+                        format!(
+                            "No command for run {} in the manifest or \
+                            `$ command` line in the output",
+                            i + 1
+                        )
+                    })?;
+                Ok(Step {
+                    command,
+                    outcome: run.outcome.or(self.outcome),
+                    stdin: run.stdin.or_else(|| self.stdin.clone()),
+                    prompt: run.prompt.or_else(|| self.prompt.clone()),
+                    preserve: run.preserve.or_else(|| self.preserve.clone()),
+                })
+            })
+            .collect()
+    }
+}
+
+/// The settings for one of the commands an output shows, in a `[[run]]` table.
+/// Any which aren't set come from the top level of the manifest.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Run {
+    pub command: Option<String>,
+    pub outcome: Option<Outcome>,
+    pub stdin: Option<String>,
+    pub prompt: Option<String>,
+    pub preserve: Option<String>,
+}
+
+/// A command to run to produce a listing's output, with everything needed to
+/// run it worked out by [`Manifest::steps`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    pub command: String,
+    pub outcome: Option<Outcome>,
+    pub stdin: Option<String>,
+    pub prompt: Option<String>,
+    pub preserve: Option<String>,
+}
+
+impl Step {
+    /// A step which just runs `command`.
+    pub fn new(command: &str) -> Step {
+        Step {
+            command: command.to_string(),
+            outcome: None,
+            stdin: None,
+            prompt: None,
+            preserve: None,
+        }
+    }
+}
+
+/// What happens when running a listing's command.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Outcome {
+    /// The command succeeds: it builds, or the program runs to completion, or
+    /// the tests all pass.
+    Compiles,
+    /// The listing does not compile, or Cargo fails some other way.
+    Fails,
+    /// The program compiles but panics.
+    Panics,
+    /// The tests compile but at least one fails.
+    TestFails,
+}
+
+impl Outcome {
+    /// Work out what happened from whether the command succeeded and what it
+    /// printed.
+    pub fn of(success: bool, output: &str) -> Outcome {
+        if success {
+            Outcome::Compiles
+        } else if output.contains("error: could not compile") {
+            Outcome::Fails
+        } else if output.contains("test result: FAILED") {
+            Outcome::TestFails
+        } else if output.contains("panicked at") {
+            Outcome::Panics
+        } else {
+            Outcome::Fails
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Outcome::Compiles => "compiles",
+            Outcome::Fails => "fails",
+            Outcome::Panics => "panics",
+            Outcome::TestFails => "test-fails",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn parses_every_field() {
+        let manifest = Manifest::parse(
+            r#"
+command = "cargo run"
+outcome = "test-fails"
+stdin = "5\n"
+prompt = "Guess:"
+preserve = 'secret: (\d+)'
+setup = ["cargo build"]
+rustfmt = false
+
+[env]
+RUST_LOG = "debug"
+
+[[run]]
+stdin = "6\n"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            manifest,
+            Manifest {
+                command: Some(String::from("cargo run")),
+                outcome: Some(Outcome::TestFails),
+                stdin: Some(String::from("5\n")),
+                prompt: Some(String::from("Guess:")),
+                preserve: Some(String::from(r"secret: (\d+)")),
+                setup: vec![String::from("cargo build")],
+                run: vec![Run {
+                    stdin: Some(String::from("6\n")),
+                    ..Run::default()
+                }],
+                env: BTreeMap::from([(
+                    String::from("RUST_LOG"),
+                    String::from("debug")
+                )]),
+                rustfmt: false,
+            }
+        );
+    }
+
+    #[test]
+    fn missing_fields_get_defaults() {
+        assert_eq!(Manifest::parse("").unwrap(), Manifest::default());
+        assert!(Manifest::default().rustfmt);
+    }
+
+    #[test]
+    fn rejects_unknown_fields_and_outcomes() {
+        assert!(Manifest::parse("comand = \"cargo run\"").is_err());
+        assert!(Manifest::parse("outcome = \"explodes\"").is_err());
+    }
+
+    #[test]
+    fn load_defaults_when_there_is_no_manifest() {
+        let dir = TempDir::new("manifest");
+        assert_eq!(Manifest::load(&dir).unwrap(), Manifest::default());
+
+        fs::write(dir.join(FILE_NAME), "outcome = \"panics\"").unwrap();
+        assert_eq!(
+            Manifest::load(&dir).unwrap().outcome,
+            Some(Outcome::Panics)
+        );
+
+        fs::write(dir.join(FILE_NAME), "outcome = panics").unwrap();
+        let error = Manifest::load(&dir).unwrap_err().to_string();
+        assert!(error.starts_with("Invalid manifest"), "{error}");
+    }
+
+    #[test]
+    fn steps_come_from_the_output_without_a_manifest() {
+        let manifest = Manifest::default();
+        assert_eq!(
+            manifest.steps(&["cargo run", "cargo test"]).unwrap(),
+            vec![Step::new("cargo run"), Step::new("cargo test")]
+        );
+        assert!(manifest.steps(&[]).is_err());
+
+        let manifest = Manifest {
+            command: Some(String::from("cargo build")),
+            ..Manifest::default()
+        };
+        assert_eq!(
+            manifest.steps(&["cargo run", "cargo run"]).unwrap(),
+            vec![Step::new("cargo build")]
+        );
+    }
+
+    #[test]
+    fn runs_fall_back_to_the_top_level_settings() {
+        let manifest = Manifest::parse(
+            r#"
+prompt = "Guess:"
+stdin = "1\n"
+outcome = "compiles"
+
+[[run]]
+
+[[run]]
+command = "cargo run --release"
+stdin = "2\n"
+outcome = "panics"
+"#,
+        )
+        .unwrap();
+
+        let steps = manifest.steps(&["cargo run"]).unwrap();
+        assert_eq!(
+            steps,
+            vec![
+                Step {
+                    stdin: Some(String::from("1\n")),
+                    prompt: Some(String::from("Guess:")),
+                    outcome: Some(Outcome::Compiles),
+                    ..Step::new("cargo run")
+                },
+                Step {
+                    stdin: Some(String::from("2\n")),
+                    prompt: Some(String::from("Guess:")),
+                    outcome: Some(Outcome::Panics),
+                    ..Step::new("cargo run --release")
+                },
+            ]
+        );
+
+        let error = Manifest::parse("[[run]]\n[[run]]")
+            .unwrap()
+            .steps(&["cargo run"])
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("run 2"), "{error}");
+    }
+
+    #[test]
+    fn works_out_the_outcome() {
+        assert_eq!(Outcome::of(true, "Hello, world!"), Outcome::Compiles);
+        assert_eq!(
+            Outcome::of(
+                false,
+                "error[E0384]: cannot assign twice to immutable variable `x`\n\
+                 error: could not compile `variables` (bin \"variables\") due to 1 previous error"
+            ),
+            Outcome::Fails
+        );
+        assert_eq!(
+            Outcome::of(
+                false,
+                "thread 'main' (1) panicked at src/main.rs:2:5:\ncrash and burn"
+            ),
+            Outcome::Panics
+        );
+        assert_eq!(
+            Outcome::of(
+                false,
+                "thread 'tests::another' (1) panicked at src/lib.rs:17:9:\n\
+                 test result: FAILED. 1 passed; 1 failed;"
+            ),
+            Outcome::TestFails
+        );
+        assert_eq!(
+            Outcome::of(false, "error: a bin target must be available"),
+            Outcome::Fails
+        );
+    }
+}
@@ -0,0 +1,375 @@
+//! Compare two builds of the book to see what some change did to it, like
+//! updating the toolchain or mdBook.
+//!
+//! Only the content of each page is compared: everything mdBook generates
+//! around it, like the sidebar, search index, and assets, is left out, as is
+//! `print.html`, which repeats every other page. Pages are matched up by their
+//! path relative to the book's root, and a page whose content only differs in
+//! whitespace between tags is reported as such rather than as a change. For
+//! the rest, the report shows a diff of the code blocks and output on the page,
+//! which is what most changes to the tooling affect.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use similar::TextDiff;
+
+/// Pages mdBook generates from other pages rather than from a chapter.
+const GENERATED_PAGES: &[&str] = &["print.html", "toc.html"];
+
+lazy_static! {
+    static ref MAIN: Regex = Regex::new(r"(?s)<main>(.*)</main>").unwrap();
+    static ref CODE_BLOCK: Regex =
+        Regex::new(r"(?s)<pre[^>]*>\s*<code[^>]*>(.*?)</code>\s*</pre>")
+            .unwrap();
+    static ref TAG: Regex = Regex::new(r"<[^>]*>").unwrap();
+    static ref BETWEEN_TAGS: Regex = Regex::new(r">\s+<").unwrap();
+    static ref WHITESPACE: Regex = Regex::new(r"\s+").unwrap();
+}
+
+/// How a page changed between the two books.
+#[derive(Debug, PartialEq)]
+pub enum Change {
+    /// The code blocks changed; this is a diff of their text.
+    Code(String),
+    /// The code blocks are the same, but the text around them changed.
+    Prose,
+    /// Only whitespace changed.
+    Whitespace,
+}
+
+impl Change {
+    fn is_code(&self) -> bool {
+        matches!(self, Change::Code(_))
+    }
+
+    fn is_prose(&self) -> bool {
+        matches!(self, Change::Prose)
+    }
+
+    fn is_whitespace(&self) -> bool {
+        matches!(self, Change::Whitespace)
+    }
+}
+
+/// Compare the same page from each book, or return `None` if its content did
+/// not change.
+pub fn compare_page(before: &str, after: &str) -> Option<Change> {
+    let before = content(before);
+    let after = content(after);
+    if before == after {
+        return None;
+    }
+
+    // Put a blank line between blocks so it's clear in the diff where each
+    // one ends.
+    let before_code = code_blocks(before).join("\n");
+    let after_code = code_blocks(after).join("\n");
+    if before_code != after_code {
+        let diff = TextDiff::from_lines(&before_code, &after_code)
+            .unified_diff()
+            .context_radius(2)
+            .to_string();
+        return Some(Change::Code(diff));
+    }
+
+    if collapse_whitespace(before) == collapse_whitespace(after) {
+        Some(Change::Whitespace)
+    } else {
+        Some(Change::Prose)
+    }
+}
+
+/// The part of a page mdBook rendered from its chapter, without the sidebar,
+/// menus, and scripts around it.
+pub fn content(html: &str) -> &str {
+    MAIN.captures(html)
+        .map_or(html, |caps| caps.get(1).unwrap().as_str())
+}
+
+/// The text of each code block in `html`, as it would be shown, each ending
+/// with a newline.
+pub fn code_blocks(html: &str) -> Vec<String> {
+    CODE_BLOCK
+        .captures_iter(html)
+        .map(|caps| {
+            let mut text = unescape(&TAG.replace_all(&caps[1], ""));
+            if !text.ends_with('\n') {
+                text.push('\n');
+            }
+            text
+        })
+        .collect()
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&amp;", "&")
+}
+
+fn collapse_whitespace(html: &str) -> String {
+    let html = BETWEEN_TAGS.replace_all(html, "><");
+    WHITESPACE.replace_all(html.trim(), " ").into_owned()
+}
+
+/// The pages in a built book, by their path relative to `dir` with `/`
+/// between components.
+pub fn pages(dir: &Path) -> Result<BTreeMap<String, PathBuf>, Box<dyn Error>> {
+    if !dir.is_dir() {
+        return Err(format!("'{}' is not a built book", dir.display()).into());
+    }
+    let mut pages = BTreeMap::new();
+    for entry in walkdir::WalkDir::new(dir).min_depth(1) {
+        let entry = entry?;
+        let path = entry.path();
+        if !entry.file_type().is_file()
+            || path.extension().is_none_or(|ext| ext != "html")
+        {
+            continue;
+        }
+        let relative = path
+            .strip_prefix(dir)?
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if GENERATED_PAGES.contains(&relative.as_str()) {
+            continue;
+        }
+        pages.insert(relative, path.to_path_buf());
+    }
+    Ok(pages)
+}
+
+/// Every difference between two builds of the book.
+#[derive(Debug, Default)]
+pub struct Report {
+    /// How many pages are in both books.
+    pub compared: usize,
+    pub changed: BTreeMap<String, Change>,
+    /// Pages only in the book from after the change.
+    pub added: Vec<String>,
+    /// Pages only in the book from before the change.
+    pub removed: Vec<String>,
+}
+
+impl Report {
+    /// Compare the book built in `before` with the one built in `after`.
+    pub fn new(before: &Path, after: &Path) -> Result<Report, Box<dyn Error>> {
+        let before_pages = pages(before)?;
+        let mut after_pages = pages(after)?;
+
+        let mut report = Report::default();
+        for (page, before_path) in before_pages {
+            let Some(after_path) = after_pages.remove(&page) else {
+                report.removed.push(page);
+                continue;
+            };
+            report.compared += 1;
+            let before = fs::read_to_string(&before_path).map_err(|e| {
+                format!("Could not read '{}': {e}", before_path.display())
+            })?;
+            let after = fs::read_to_string(&after_path).map_err(|e| {
+                format!("Could not read '{}': {e}", after_path.display())
+            })?;
+            if let Some(change) = compare_page(&before, &after) {
+                report.changed.insert(page, change);
+            }
+        }
+        report.added = after_pages.into_keys().collect();
+        Ok(report)
+    }
+
+    /// The pages whose content changed in the way `kind` checks for.
+    fn changed(&self, kind: fn(&Change) -> bool) -> Vec<&str> {
+        self.changed
+            .iter()
+            .filter(|(_, change)| kind(change))
+            .map(|(page, _)| page.as_str())
+            .collect()
+    }
+
+    /// One line saying how many pages changed in which ways.
+    pub fn summary(&self) -> String {
+        format!(
+            "Compared {} pages: {} with changed code, {} with changed prose, \
+            {} with only whitespace changes, {} added, {} removed.",
+            self.compared,
+            self.changed(Change::is_code).len(),
+            self.changed(Change::is_prose).len(),
+            self.changed(Change::is_whitespace).len(),
+            self.added.len(),
+            self.removed.len(),
+        )
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.summary())?;
+
+        list(f, "Code changed", &self.changed(Change::is_code))?;
+        list(f, "Prose changed", &self.changed(Change::is_prose))?;
+        list(
+            f,
+            "Only whitespace changed",
+            &self.changed(Change::is_whitespace),
+        )?;
+        list(f, "Added", &self.added)?;
+        list(f, "Removed", &self.removed)?;
+
+        for (page, change) in &self.changed {
+            if let Change::Code(diff) = change {
+                writeln!(f, "\n=== {page} ===\n{diff}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn list(
+    f: &mut fmt::Formatter<'_>,
+    heading: &str,
+    pages: &[impl AsRef<str>],
+) -> fmt::Result {
+    if pages.is_empty() {
+        return Ok(());
+    }
+    writeln!(f, "\n{heading}:")?;
+    for page in pages {
+        writeln!(f, "  {}", page.as_ref())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    fn page(main: &str) -> String {
+        format!(
+            "<html><nav>{{{{sidebar}}}}</nav><main>{main}</main>\
+             <script src=\"book.js\"></script></html>"
+        )
+    }
+
+    #[test]
+    fn only_the_content_is_compared() {
+        let before = page("<p>Hello</p>");
+        let after = before.replace("sidebar", "new sidebar");
+        assert_eq!(compare_page(&before, &after), None);
+    }
+
+    #[test]
+    fn classifies_changes() {
+        let before = page(
+            "<h1>Hi</h1>\n<p>Some text</p>\n\
+             <pre><code class=\"language-console\">$ cargo run\n   \
+             Compiling hello v0.1.0\n</code></pre>",
+        );
+
+        let after = before.replace("<h1>Hi</h1>\n<p>", "<h1>Hi</h1>  <p>");
+        assert_eq!(compare_page(&before, &after), Some(Change::Whitespace));
+
+        let after = before.replace("Some text", "Other text");
+        assert_eq!(compare_page(&before, &after), Some(Change::Prose));
+
+        let after = before.replace("hello v0.1.0", "hello v0.2.0");
+        let Some(Change::Code(diff)) = compare_page(&before, &after) else {
+            panic!("code should have changed");
+        };
+        assert!(diff.contains("-   Compiling hello v0.1.0\n"), "{diff}");
+        assert!(diff.contains("+   Compiling hello v0.2.0\n"), "{diff}");
+    }
+
+    #[test]
+    fn code_blocks_are_shown_as_text() {
+        let html = "<pre class=\"playground\"><code class=\"language-rust\">\
+                    <span class=\"boring\">fn main() {\n</span>\
+                    let s = &quot;a &amp;&amp; b &lt; c&quot;;\n\
+                    <span class=\"boring\">}</span></code></pre>\
+                    <p>between</p>\
+                    <pre><code>second</code></pre>";
+        assert_eq!(
+            code_blocks(html),
+            vec![
+                "fn main() {\nlet s = \"a && b < c\";\n}\n".to_string(),
+                "second\n".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn pages_are_compared_by_path() {
+        let root = TempDir::new("megadiff");
+        let before = root.join("before");
+        let after = root.join("after");
+        for (dir, nested) in [(&before, "<p>One</p>"), (&after, "<p>Two</p>")] {
+            fs::create_dir_all(dir.join("nested")).unwrap();
+            fs::create_dir_all(dir.join("css")).unwrap();
+            fs::write(dir.join("index.html"), page("<p>Same</p>")).unwrap();
+            fs::write(dir.join("nested/index.html"), page(nested)).unwrap();
+            fs::write(dir.join("print.html"), page(nested)).unwrap();
+            fs::write(dir.join("css/general.css"), nested).unwrap();
+        }
+        fs::write(before.join("old.html"), page("")).unwrap();
+        fs::write(after.join("new.html"), page("")).unwrap();
+
+        let report = Report::new(&before, &after).unwrap();
+        assert_eq!(report.compared, 2);
+        assert_eq!(
+            report.changed.into_iter().collect::<Vec<_>>(),
+            vec![(String::from("nested/index.html"), Change::Prose)]
+        );
+        assert_eq!(report.added, vec!["new.html"]);
+        assert_eq!(report.removed, vec!["old.html"]);
+
+        assert!(Report::new(&root.join("missing"), &after).is_err());
+    }
+
+    #[test]
+    fn report_lists_pages_and_diffs_code() {
+        let report = Report {
+            compared: 3,
+            changed: BTreeMap::from([
+                (
+                    String::from("a.html"),
+                    Change::Code(String::from("-x\n+y\n")),
+                ),
+                (String::from("b.html"), Change::Whitespace),
+            ]),
+            added: vec![String::from("c.html")],
+            removed: Vec::new(),
+        };
+        assert_eq!(
+            report.to_string(),
+            "Compared 3 pages: 1 with changed code, 0 with changed prose, \
+             1 with only whitespace changes, 1 added, 0 removed.
+
+Code changed:
+  a.html
+
+Only whitespace changed:
+  b.html
+
+Added:
+  c.html
+
+=== a.html ===
+-x
++y
+
+"
+        );
+    }
+}
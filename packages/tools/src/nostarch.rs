@@ -0,0 +1,176 @@
+//! The transformations which turn the Markdown rendered by mdBook into the
+//! Markdown we send to No Starch Press for print.
+
+use crate::{
+    cleanup_blockquotes, link2print, remove_hidden_lines, remove_links,
+    remove_markup,
+};
+
+/// One transformation applied to each rendered Markdown file.
+///
+/// The order of [`Stage::ALL`] is the order the stages run in, and stages are
+/// numbered from 1 in that order (stage 0 being the rendered input).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    /// Remove links marked `<!-- ignore -->`; see [`remove_links`].
+    RemoveLinks,
+    /// Turn the remaining links into inline text; see [`link2print`].
+    Link2Print,
+    /// Remove code fence info strings and filename/caption spans; see
+    /// [`remove_markup`].
+    RemoveMarkup,
+    /// Remove lines rustdoc hides; see [`remove_hidden_lines`].
+    RemoveHiddenLines,
+    /// Fix up block quotes mangled by the preprocessors; see
+    /// [`cleanup_blockquotes`].
+    CleanupBlockquotes,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 5] = [
+        Stage::RemoveLinks,
+        Stage::Link2Print,
+        Stage::RemoveMarkup,
+        Stage::RemoveHiddenLines,
+        Stage::CleanupBlockquotes,
+    ];
+
+    /// The name of the stage, matching the name of its standalone binary.
+    pub fn name(self) -> &'static str {
+        match self {
+            Stage::RemoveLinks => "remove_links",
+            Stage::Link2Print => "link2print",
+            Stage::RemoveMarkup => "remove_markup",
+            Stage::RemoveHiddenLines => "remove_hidden_lines",
+            Stage::CleanupBlockquotes => "cleanup_blockquotes",
+        }
+    }
+
+    pub fn apply(self, input: &str) -> String {
+        match self {
+            Stage::RemoveLinks => remove_links(input),
+            Stage::Link2Print => link2print(input),
+            Stage::RemoveMarkup => remove_markup(input),
+            Stage::RemoveHiddenLines => remove_hidden_lines(input),
+            Stage::CleanupBlockquotes => cleanup_blockquotes(input),
+        }
+    }
+}
+
+/// The sequence of [`Stage`]s to run on each file, any of which may be
+/// disabled. By default, every stage is enabled.
+#[derive(Clone, Debug)]
+pub struct Pipeline {
+    enabled: [bool; Stage::ALL.len()],
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Pipeline {
+            enabled: [true; Stage::ALL.len()],
+        }
+    }
+}
+
+impl Pipeline {
+    pub fn set_enabled(&mut self, stage: Stage, enabled: bool) {
+        self.enabled[stage as usize] = enabled;
+    }
+
+    pub fn is_enabled(&self, stage: Stage) -> bool {
+        self.enabled[stage as usize]
+    }
+
+    /// Run every enabled stage, in order.
+    pub fn run(&self, input: &str) -> String {
+        self.run_through(input, Stage::ALL.len())
+    }
+
+    /// Run the enabled stages up to and including stage number `last`, so that
+    /// `run_through(input, 0)` returns the input unchanged.
+    pub fn run_through(&self, input: &str, last: usize) -> String {
+        Stage::ALL
+            .iter()
+            .take(last)
+            .filter(|&&stage| self.is_enabled(stage))
+            .fold(input.to_string(), |text, stage| stage.apply(&text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Pipeline, Stage};
+
+    const RENDERED: &str = r#"## Storing Values
+
+See [the docs](https://doc.rust-lang.org/std/)<!-- ignore --> and the
+[reference][ref].
+
+<span class="filename">Filename: src/main.rs</span>
+
+```rust,ignore
+# use std::io;
+fn main() {
+    let v = vec![1];
+}
+```
+
+ >
+ > Note: A note.
+
+[ref]: https://doc.rust-lang.org/reference/
+"#;
+
+    #[test]
+    fn runs_all_stages_in_order() {
+        let expected = r#"## Storing Values
+
+See the docs and the
+reference at *https://doc.rust-lang.org/reference/*.
+
+Filename: src/main.rs
+
+```
+fn main() {
+    let v = vec![1];
+}
+```
+
+> Note: A note.
+"#;
+        // `remove_markup` and `remove_hidden_lines` work line by line and do
+        // not keep the trailing newline.
+        assert_eq!(Pipeline::default().run(RENDERED), expected.trim_end());
+    }
+
+    #[test]
+    fn ignored_links_are_removed_before_links_are_printed() {
+        let input = "[a](b)<!-- ignore --> and [c](d)";
+        let output = Pipeline::default().run(input);
+        assert_eq!(output, "a and c at *d*");
+    }
+
+    #[test]
+    fn disabled_stages_are_skipped() {
+        let mut pipeline = Pipeline::default();
+        pipeline.set_enabled(Stage::RemoveHiddenLines, false);
+        pipeline.set_enabled(Stage::Link2Print, false);
+
+        let output = pipeline.run(RENDERED);
+        assert!(output.contains("# use std::io;"));
+        assert!(output.contains("[reference][ref]"));
+        assert!(!output.contains("<!-- ignore -->"));
+        assert!(!output.contains(r#"<span class="filename">"#));
+    }
+
+    #[test]
+    fn running_through_a_stage_stops_after_it() {
+        let pipeline = Pipeline::default();
+        assert_eq!(pipeline.run_through(RENDERED, 0), RENDERED);
+
+        let after_links = pipeline.run_through(RENDERED, 2);
+        assert!(after_links.contains("reference at *https://doc"));
+        assert!(after_links.contains("```rust,ignore"));
+        assert!(after_links.contains("# use std::io;"));
+    }
+}
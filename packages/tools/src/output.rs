@@ -0,0 +1,1143 @@
+//! Regenerate the `output.txt` files which show the result of building or
+//! running a listing.
+//!
+//! The first line of each `output.txt` is the command to run, like
+//! `$ cargo run`. Everything after it is what that command printed, normalized
+//! so that it does not depend on who ran it or where: see [`normalize`].
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::io::{self, ErrorKind, Read, Write};
+use std::path::Path;
+use std::process::{ChildStdin, Command, ExitStatus, Stdio};
+use std::thread;
+
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use sha2::{Digest, Sha256};
+use similar::TextDiff;
+
+use crate::manifest::{Manifest, Outcome, Step};
+
+/// Flags passed to `rustc` for every listing, to cut down on warnings which
+/// are not the point of the listing.
+pub const RUSTFLAGS: &str = "-A unused_variables -A dead_code";
+
+lazy_static! {
+    static ref COMMAND: Regex = Regex::new(r"(?m)^\$ (.*)$").unwrap();
+    static ref COMMAND_LINE: Regex = Regex::new(r"(?m)^\$ .*\n?").unwrap();
+    static ref RUNNING: Regex = Regex::new(r"(?m)^ +Running `.*\n").unwrap();
+    static ref COMPILING: Regex =
+        Regex::new(r"(Compiling|Checking) ([^)\s]*) v0\.1\.0 \(.*\)").unwrap();
+    static ref BLOCKING: Regex =
+        Regex::new(r"(?m)^ *Blocking waiting for file lock on .*\n").unwrap();
+    static ref TOOLCHAIN_PATH: Regex = Regex::new(
+        r"(?m)^(\s*-->\s+).*(\.rustup/toolchains/\d+\.\d+)[^/]*"
+    )
+    .unwrap();
+    static ref MIRI_PATH: Regex = Regex::new(
+        r"Running `.*\.rustup/toolchains/nightly[^/]*/bin/cargo-miri runner target/miri/[^/]*/debug/([^/`]*)"
+    )
+    .unwrap();
+    static ref COMPILE_TIME: Regex = Regex::new(
+        r"Finished `(dev|test)` profile \[unoptimized \+ debuginfo\] target\(s\) in ([0-9.]+)"
+    )
+    .unwrap();
+    static ref TEST_HASH: Regex = Regex::new(
+        r"Running (\S+)( [^\s()]+)? \(target/debug/deps/([^-\s]*)-([0-9a-f]+)\)"
+    )
+    .unwrap();
+    static ref PANIC_PID: Regex =
+        Regex::new(r"thread '([^']*)' \((\d+)\) panicked at").unwrap();
+    static ref TEST_TIME: Regex =
+        Regex::new(r"(test result: .*; finished in )(\d+\.\d+)s").unwrap();
+}
+
+/// The command an `output.txt` shows the output of, without the leading `$ `.
+pub fn command(output: &str) -> Option<&str> {
+    COMMAND
+        .captures(output)
+        .map(|caps| caps.get(1).unwrap().as_str().trim())
+}
+
+/// Every command an `output.txt` shows the output of, in order.
+pub fn commands(output: &str) -> Vec<&str> {
+    COMMAND
+        .captures_iter(output)
+        .map(|caps| caps.get(1).unwrap().as_str().trim())
+        .collect()
+}
+
+/// What each command in an `output.txt` printed, in order.
+fn sections(output: &str) -> Vec<&str> {
+    let starts: Vec<_> = COMMAND_LINE.find_iter(output).collect();
+    starts
+        .iter()
+        .enumerate()
+        .map(|(i, start)| {
+            let end =
+                starts.get(i + 1).map_or(output.len(), |next| next.start());
+            &output[start.end()..end]
+        })
+        .collect()
+}
+
+/// Values from a previous run which change every time the command runs. We
+/// keep the old ones to avoid churn in the diff when regenerating the output.
+#[derive(Debug, Default, PartialEq)]
+pub struct Preserved {
+    /// How long the first build took in seconds, e.g. `0.52`.
+    pub compile_time: Option<String>,
+    /// The hash of the first test binary.
+    pub test_hash: Option<String>,
+    /// The thread ID of each panic, in order.
+    pub panic_pids: Vec<String>,
+    /// How long each set of tests took to run in seconds, in order.
+    pub test_times: Vec<String>,
+}
+
+impl Preserved {
+    pub fn from_output(previous: &str) -> Preserved {
+        Preserved {
+            compile_time: COMPILE_TIME
+                .captures(previous)
+                .map(|caps| caps[2].to_string()),
+            test_hash: TEST_HASH
+                .captures(previous)
+                .map(|caps| caps[4].to_string()),
+            panic_pids: PANIC_PID
+                .captures_iter(previous)
+                .map(|caps| caps[2].to_string())
+                .collect(),
+            test_times: TEST_TIME
+                .captures_iter(previous)
+                .map(|caps| caps[2].to_string())
+                .collect(),
+        }
+    }
+}
+
+/// Make the raw output of a command independent of the machine it ran on, and
+/// restore the values in `preserved`.
+///
+/// - Messages about waiting for other builds running at the same time are
+///   removed.
+/// - Paths to the crate being built become `file:///projects/<crate>`.
+/// - Paths into rustup's toolchains become `file:///home/.rustup/toolchains`,
+///   with the version but not the target triple, and likewise for Miri's
+///   runner.
+/// - Compile times, test binary hashes, panicking thread IDs, and test run
+///   times are replaced with the ones from `preserved`, if it has any.
+pub fn normalize(raw: &str, preserved: &Preserved) -> String {
+    let output = BLOCKING.replace_all(raw, "");
+
+    let output =
+        COMPILING.replace_all(&output, "$1 $2 v0.1.0 (file:///projects/$2)");
+
+    let output = TOOLCHAIN_PATH.replace_all(&output, "${1}file:///home/$2");
+
+    let output = MIRI_PATH.replace_all(
+        &output,
+        "Running `file:///home/.rustup/toolchains/nightly/bin/cargo-miri runner target/miri/debug/$1",
+    );
+
+    let output = match &preserved.compile_time {
+        Some(time) => COMPILE_TIME.replace_all(&output, |caps: &Captures| {
+            format!(
+                "Finished `{}` profile [unoptimized + debuginfo] target(s) in {time}",
+                &caps[1]
+            )
+        }),
+        None => output,
+    };
+
+    let output = match &preserved.test_hash {
+        Some(hash) => TEST_HASH.replace_all(&output, |caps: &Captures| {
+            format!(
+                "Running {}{} (target/debug/deps/{}-{hash})",
+                &caps[1],
+                caps.get(2).map_or("", |m| m.as_str()),
+                &caps[3],
+            )
+        }),
+        None => output,
+    };
+
+    let output = restore_in_order(
+        &PANIC_PID,
+        &output,
+        &preserved.panic_pids,
+        |caps, pid| format!("thread '{}' ({pid}) panicked at", &caps[1]),
+    );
+
+    let output = restore_in_order(
+        &TEST_TIME,
+        &output,
+        &preserved.test_times,
+        |caps, time| format!("{}{time}s", &caps[1]),
+    );
+
+    output.into_owned()
+}
+
+/// Replace the second group of each match of `regex` with the preserved values
+/// in order. If there are more matches now than there used to be, reuse the
+/// last preserved value rather than a new one.
+fn restore_in_order<'a>(
+    regex: &Regex,
+    output: &'a str,
+    preserved: &[String],
+    replacement: impl Fn(&Captures, &str) -> String,
+) -> Cow<'a, str> {
+    let mut values = preserved.iter();
+    let mut last = None;
+    regex.replace_all(output, |caps: &Captures| {
+        if let Some(value) = values.next() {
+            last = Some(value);
+        }
+        let value = last.map_or(&caps[2], |value| value.as_str());
+        replacement(caps, value)
+    })
+}
+
+/// What running a listing's command printed, and whether it succeeded.
+#[derive(Debug)]
+pub struct Ran {
+    /// Everything the command printed to stdout and stderr, interleaved.
+    pub output: String,
+    pub status: ExitStatus,
+}
+
+impl Ran {
+    pub fn outcome(&self) -> Outcome {
+        Outcome::of(self.status.success(), &self.output)
+    }
+}
+
+/// Run a step's command in `dir` the way the book's output is generated, with
+/// its input and the environment variables in `env`.
+///
+/// A command which fails is not an error: plenty of listings are meant to
+/// fail. Only being unable to run the command at all is.
+///
+/// If `target_dir` is given, Cargo puts build artifacts there instead of in
+/// the listing's own `target` directory.
+pub fn run(
+    step: &Step,
+    dir: &Path,
+    target_dir: Option<&Path>,
+    env: &BTreeMap<String, String>,
+) -> Result<Ran, Box<dyn Error>> {
+    let command = &step.command;
+    let mut words = command.split_whitespace();
+    let program = words
+        .next()
+        .ok_or_else(|| format!("Empty command in '{}'", dir.display()))?;
+
+    let mut cmd = Command::new(program);
+    cmd.args(words);
+    match target_dir {
+        Some(target_dir) => cmd.env("CARGO_TARGET_DIR", target_dir),
+        None => cmd.env_remove("CARGO_TARGET_DIR"),
+    };
+    // Programs waiting for input which isn't coming should see the end of it
+    // rather than hang.
+    cmd.stdin(match step.stdin {
+        Some(_) => Stdio::piped(),
+        None => Stdio::null(),
+    });
+
+    let (mut reader, writer) = std::io::pipe()?;
+    let mut child = cmd
+        .current_dir(dir)
+        .env("RUSTFLAGS", RUSTFLAGS)
+        // Run tests one at a time so they are always listed in the same order.
+        .env("RUST_TEST_THREADS", "1")
+        // The book shows the default panic message, not a backtrace.
+        .env_remove("RUST_BACKTRACE")
+        .envs(env)
+        .stdout(writer.try_clone()?)
+        .stderr(writer)
+        .spawn()
+        .map_err(|e| {
+            format!("Could not run `{command}` in '{}': {e}", dir.display())
+        })?;
+    // The `Command` holds on to the write end of the pipe until it is dropped,
+    // and the reads below only finish once every write end is closed.
+    drop(cmd);
+
+    let mut output = Vec::new();
+    match (child.stdin.take(), &step.stdin, &step.prompt) {
+        (Some(stdin), Some(text), Some(prompt)) => {
+            if let Some(asked) =
+                converse(&mut reader, stdin, text, prompt, &mut output)?
+            {
+                // The program was asked to stop, like someone pressing Ctrl-C
+                // at the prompt, so it may have died before it was killed.
+                let _ = child.kill();
+                io::copy(&mut reader, &mut io::sink())?;
+                output.truncate(asked);
+            }
+        }
+        (Some(mut stdin), Some(text), None) => {
+            // Write the input from another thread, in case the program fills
+            // up the output pipe before it has read it all.
+            let text = text.clone();
+            let input = thread::spawn(move || {
+                // The program may exit without reading all its input, which
+                // is fine.
+                let _ = stdin.write_all(text.as_bytes());
+            });
+            reader.read_to_end(&mut output)?;
+            input.join().expect("writing stdin should not panic");
+        }
+        _ => {
+            reader.read_to_end(&mut output)?;
+        }
+    }
+    let status = child.wait()?;
+
+    Ok(Ran {
+        output: String::from_utf8(output)?,
+        status,
+    })
+}
+
+/// Read everything the program prints into `output`, typing the next line of
+/// `text` each time it prints a line with `prompt`, and closing its input once
+/// there are no more. Each line typed is added to the output too, since that is
+/// where it shows up in a terminal.
+///
+/// A program which prompts again after its input is closed, rather than
+/// stopping, might never finish, like a game that ignores input it can't
+/// parse. Then this stops reading and returns the length of the output up to
+/// the end of the first prompt that went unanswered, for the caller to stop
+/// the program and cut its output there.
+fn converse(
+    reader: &mut impl Read,
+    stdin: ChildStdin,
+    text: &str,
+    prompt: &str,
+    output: &mut Vec<u8>,
+) -> Result<Option<usize>, Box<dyn Error>> {
+    let prompt = format!("{prompt}\n");
+    let mut lines = text.lines();
+    let mut stdin = Some(stdin);
+    // Where to look for the next prompt from, so it isn't answered twice.
+    let mut searched = 0;
+    let mut unanswered = None;
+    let mut buffer = [0; 4096];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(None);
+        }
+        output.extend_from_slice(&buffer[..read]);
+
+        while let Some(found) = output[searched..]
+            .windows(prompt.len())
+            .position(|window| window == prompt.as_bytes())
+        {
+            searched += found + prompt.len();
+            let Some(input) = stdin.as_mut() else {
+                return Ok(unanswered);
+            };
+            match lines.next() {
+                Some(line) => {
+                    output.extend_from_slice(line.as_bytes());
+                    output.push(b'\n');
+                    searched = output.len();
+                    // The program may exit before reading this, which is
+                    // fine.
+                    let _ = input.write_all(format!("{line}\n").as_bytes());
+                }
+                None => {
+                    stdin = None;
+                    unanswered = Some(searched);
+                }
+            }
+        }
+    }
+}
+
+/// What to show for a step with a `preserve` pattern, for programs which do
+/// something random: Cargo building the program, from its `output` this time,
+/// followed by what the program printed in its `previous` output.
+///
+/// Reproducing what a random program printed would mean running it until it
+/// happens to do the same thing again, which could take any number of runs.
+/// Instead, the new output only has to have the same shape as the old: the
+/// same up to and including the `preserve` pattern, apart from the random
+/// value it matches. Changes to what the program prints after that aren't
+/// noticed, so its output must be updated by hand.
+fn keep_random_output(
+    step: &Step,
+    previous: &str,
+    output: &str,
+) -> Result<String, Box<dyn Error>> {
+    let Some(pattern) = &step.preserve else {
+        return Ok(output.to_string());
+    };
+    let preserve = Regex::new(pattern)
+        .map_err(|e| format!("Invalid preserve pattern `{pattern}`: {e}"))?;
+    // Everything up to the end of the match, without the random value.
+    let shape = |output: &str| -> Option<(String, String)> {
+        let caps = preserve.captures(output)?;
+        let value = caps.get(1).unwrap_or_else(|| caps.get(0).unwrap());
+        let end = caps.get(0).unwrap().end();
+        Some((
+            output[..value.start()].to_string(),
+            output[value.end()..end].to_string(),
+        ))
+    };
+
+    let (_, kept) = split_at_running(previous);
+    let Some(expected) = shape(kept) else {
+        // Without a previous value there is nothing to keep.
+        return Ok(output.to_string());
+    };
+    let (building, program) = split_at_running(output);
+    let program = normalize(program, &Preserved::from_output(previous));
+    if shape(&program).as_ref() != Some(&expected) {
+        return Err(format!(
+            "`{}` no longer prints what it did before up to `{pattern}`, so \
+            its output can't be kept; update it by hand:\n{program}",
+            step.command
+        )
+        .into());
+    }
+    Ok(format!("{building}{kept}"))
+}
+
+/// Split what `cargo run` printed into what Cargo printed, up to and including
+/// the line saying it is running the program, and what the program printed.
+fn split_at_running(output: &str) -> (&str, &str) {
+    match RUNNING.find(output) {
+        Some(running) => output.split_at(running.end()),
+        None => ("", output),
+    }
+}
+
+/// Run `cargo clean` in `dir`, so the next build starts from scratch.
+pub fn clean(dir: &Path) -> Result<(), Box<dyn Error>> {
+    let status = Command::new("cargo")
+        .arg("clean")
+        .current_dir(dir)
+        .output()
+        .map_err(|e| format!("Could not run cargo clean: {e}"))?
+        .status;
+    if !status.success() {
+        return Err(format!(
+            "cargo clean failed in '{}': {status}",
+            dir.display()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Where the artifacts from building a listing go.
+#[derive(Clone, Copy, Debug)]
+pub enum TargetDir<'a> {
+    /// The listing's own `target` directory, which is cleaned before and after
+    /// building so the output shows everything being compiled.
+    Own,
+    /// A directory shared with other listings, which must be able to share it
+    /// without changing their output; see [`can_share_target_dir`]. It must
+    /// not be used by more than one build at a time, since waiting for
+    /// Cargo's lock on it would show up in the output.
+    Shared(&'a Path),
+}
+
+/// Produce the new contents of an `output.txt` by building the listing in
+/// `build_dir` from scratch and running its commands: the ones in its
+/// `manifest`, or else the ones in `previous`, the old contents of the file.
+///
+/// If the manifest says what the outcome of running a command should be,
+/// anything else is an error.
+pub fn regenerate(
+    previous: &str,
+    build_dir: &Path,
+    target_dir: TargetDir<'_>,
+    manifest: &Manifest,
+) -> Result<String, Box<dyn Error>> {
+    let steps = manifest
+        .steps(&commands(previous))
+        .map_err(|e| format!("{e} for '{}'", build_dir.display()))?;
+    let previous_sections = sections(previous);
+
+    let (target_dir, shared) = match target_dir {
+        TargetDir::Own => {
+            // Act like this is the first time this listing has been built.
+            clean(build_dir)?;
+            (None, None)
+        }
+        TargetDir::Shared(target_dir) => {
+            let target_dir = std::path::absolute(target_dir)?;
+            // Cargo prints paths in the target directory relative to the
+            // listing only when the target directory is inside it.
+            let prefix = format!("{}/", target_dir.display());
+            (Some(target_dir), Some(prefix))
+        }
+    };
+    let target_dir = target_dir.as_deref();
+
+    for command in &manifest.setup {
+        let ran =
+            run(&Step::new(command), build_dir, target_dir, &manifest.env)?;
+        if !ran.status.success() {
+            return Err(format!(
+                "Setup command `{command}` failed ({}):\n{}",
+                ran.status, ran.output
+            )
+            .into());
+        }
+    }
+
+    let mut transcript = String::new();
+    for (i, step) in steps.iter().enumerate() {
+        let previous = previous_sections.get(i).copied().unwrap_or("");
+        let mut ran = run(step, build_dir, target_dir, &manifest.env)?;
+        if let Some(prefix) = &shared {
+            ran.output = ran.output.replace(prefix, "target/");
+        }
+
+        if let Some(expected) = step.outcome {
+            let outcome = ran.outcome();
+            if outcome != expected {
+                return Err(format!(
+                    "`{}` was expected to have the outcome `{expected}` \
+                    but had `{outcome}` ({}):\n{}",
+                    step.command, ran.status, ran.output
+                )
+                .into());
+            }
+        }
+
+        // Separate the commands with a blank line, as they would be if the
+        // output was copied from a terminal with some space added.
+        if !transcript.is_empty() {
+            while !transcript.ends_with("\n\n") {
+                transcript.push('\n');
+            }
+        }
+        let output = keep_random_output(step, previous, &ran.output)?;
+        transcript.push_str(&format!(
+            "$ {}\n{}",
+            step.command,
+            normalize(&output, &Preserved::from_output(previous))
+        ));
+    }
+    // Blank lines the last command printed at the end would only add space at
+    // the bottom of the book's listing.
+    if transcript.ends_with("\n\n") {
+        transcript.truncate(transcript.trim_end_matches('\n').len() + 1);
+    }
+
+    if target_dir.is_none() {
+        clean(build_dir)?;
+    }
+    Ok(transcript)
+}
+
+/// A unified diff from the checked-in output at `path` to the output the
+/// listing produces now, or `None` if they are the same.
+pub fn drift(path: &Path, checked_in: &str, current: &str) -> Option<String> {
+    if checked_in == current {
+        return None;
+    }
+    let path = path.display().to_string();
+    Some(
+        TextDiff::from_lines(checked_in, current)
+            .unified_diff()
+            .header(&format!("a/{path}"), &format!("b/{path}"))
+            .to_string(),
+    )
+}
+
+/// Whether a listing can be built in a target directory shared with other
+/// listings without changing its output.
+///
+/// Listings with dependencies cannot, because the output shows the
+/// dependencies being compiled, which would only happen for the first listing
+/// to use them. Neither can listings built with a different toolchain than
+/// the rest. Listings with the same [`package_name`] must not share one
+/// either: Cargo gives their artifacts the same names, and can mistake one
+/// listing's artifacts for the other's being up to date.
+pub fn can_share_target_dir(cargo_toml: &str, command: &str) -> bool {
+    if command.split_whitespace().any(|word| word.starts_with('+')) {
+        return false;
+    }
+
+    let mut in_package = false;
+    let mut in_dependencies = false;
+    for line in cargo_toml.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') {
+            if line.starts_with("[workspace") {
+                return false;
+            }
+            in_package |= line == "[package]";
+            in_dependencies = line.contains("dependencies");
+        } else if in_dependencies {
+            return false;
+        }
+    }
+    in_package
+}
+
+/// The name of the package a listing's `Cargo.toml` defines, if any.
+pub fn package_name(cargo_toml: &str) -> Option<&str> {
+    let mut in_package = false;
+    for line in cargo_toml.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_package = line == "[package]";
+        } else if in_package
+            && let Some((key, value)) = line.split_once('=')
+            && key.trim() == "name"
+        {
+            return Some(value.trim().trim_matches('"'));
+        }
+    }
+    None
+}
+
+/// Hashes of what went into each listing's `output.txt` the last time it was
+/// regenerated, so that listings which have not changed since can be skipped.
+#[derive(Debug, Default, PartialEq)]
+pub struct Cache {
+    hashes: BTreeMap<String, String>,
+}
+
+impl Cache {
+    /// Load the cache from `path`, or start an empty one if there is none.
+    pub fn load(path: &Path) -> Result<Cache, Box<dyn Error>> {
+        match fs::read_to_string(path) {
+            Ok(text) => Ok(Cache::parse(&text)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Cache::default()),
+            Err(e) => {
+                Err(format!("Could not read cache '{}': {e}", path.display())
+                    .into())
+            }
+        }
+    }
+
+    /// Parse a cache written by [`Cache::save`]: one line per listing with its
+    /// hash and its path relative to the listings directory. Lines which do
+    /// not look like that are ignored, so a corrupt cache just means listings
+    /// get rebuilt.
+    pub fn parse(text: &str) -> Cache {
+        let hashes = text
+            .lines()
+            .filter_map(|line| line.split_once(' '))
+            .map(|(hash, listing)| (listing.to_string(), hash.to_string()))
+            .collect();
+        Cache { hashes }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let text: String = self
+            .hashes
+            .iter()
+            .map(|(listing, hash)| format!("{hash} {listing}\n"))
+            .collect();
+        fs::write(path, text).map_err(|e| {
+            format!("Could not write cache '{}': {e}", path.display()).into()
+        })
+    }
+
+    pub fn is_fresh(&self, listing: &str, hash: &str) -> bool {
+        self.hashes
+            .get(listing)
+            .is_some_and(|cached| cached == hash)
+    }
+
+    pub fn insert(&mut self, listing: &str, hash: String) {
+        self.hashes.insert(listing.to_string(), hash);
+    }
+}
+
+/// Hash the contents of every file in a listing's directory other than build
+/// artifacts and its `output.txt`, along with their paths relative to `dir`.
+pub fn hash_dir(dir: &Path) -> Result<String, Box<dyn Error>> {
+    let mut hasher = Sha256::new();
+    for entry in walkdir::WalkDir::new(dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| entry.file_name() != "target")
+    {
+        let entry = entry?;
+        if !entry.file_type().is_file() || entry.file_name() == "output.txt" {
+            continue;
+        }
+        let path = entry.path();
+        let relative = path.strip_prefix(dir)?.to_string_lossy();
+        let contents = fs::read(path)
+            .map_err(|e| format!("Could not read '{}': {e}", path.display()))?;
+        hasher.update(relative.as_bytes());
+        hasher.update([0]);
+        hasher.update((contents.len() as u64).to_le_bytes());
+        hasher.update(&contents);
+    }
+    Ok(hex(&hasher.finalize()))
+}
+
+/// The hash recorded in the [`Cache`] for a listing: everything which can
+/// change its output, plus the output itself so that editing an `output.txt`
+/// by hand (to change the command, say) also counts as a change.
+pub fn listing_hash(
+    sources: &str,
+    toolchain: &str,
+    trpl: Option<&str>,
+    output: &str,
+) -> String {
+    let mut hasher = Sha256::new();
+    for part in [sources, toolchain, trpl.unwrap_or(""), RUSTFLAGS, output] {
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part.as_bytes());
+    }
+    hex(&hasher.finalize())
+}
+
+/// The full version information of the toolchain a command uses when run in
+/// `dir`, which picks up any `rust-toolchain` file above it.
+pub fn toolchain_version(
+    command: &str,
+    dir: &Path,
+) -> Result<String, Box<dyn Error>> {
+    let mut rustc = Command::new("rustc");
+    if let Some(toolchain) = command
+        .split_whitespace()
+        .find(|word| word.starts_with('+'))
+    {
+        rustc.arg(toolchain);
+    }
+    let output = rustc
+        .arg("-vV")
+        .current_dir(dir)
+        .output()
+        .map_err(|e| format!("Could not run rustc: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Could not get the toolchain version for `{command}`: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )
+        .into());
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn finds_the_command() {
+        let output = "$ cargo run -- the poem.txt\n   Compiling minigrep";
+        assert_eq!(command(output), Some("cargo run -- the poem.txt"));
+        assert_eq!(command("   Compiling minigrep"), None);
+    }
+
+    #[test]
+    fn splits_transcripts_by_command() {
+        let output = "$ cargo run\n   Compiling guessing_game\nfirst\n\n\
+                      $ cargo run\nsecond\n";
+        assert_eq!(commands(output), vec!["cargo run", "cargo run"]);
+        assert_eq!(
+            sections(output),
+            vec!["   Compiling guessing_game\nfirst\n\n", "second\n"]
+        );
+        assert_eq!(sections("$ cargo build"), vec![""]);
+        assert!(sections("").is_empty());
+    }
+
+    #[test]
+    fn project_paths_point_to_projects_dir() {
+        let raw = "   Compiling guessing_game v0.1.0 (/Users/carol/rust/book/tmp/listings/ch02/listing-02-01)
+    Checking adder v0.1.0 (/home/runner/work/book/tmp/listings/ch11/no-listing-01)
+   Compiling rand v0.8.5
+";
+        let expected =
+            "   Compiling guessing_game v0.1.0 (file:///projects/guessing_game)
+    Checking adder v0.1.0 (file:///projects/adder)
+   Compiling rand v0.8.5
+";
+        assert_eq!(normalize(raw, &Preserved::default()), expected);
+    }
+
+    #[test]
+    fn waiting_for_other_builds_is_not_shown() {
+        let raw = "    Blocking waiting for file lock on package cache
+    Blocking waiting for file lock on build directory
+   Compiling adder v0.1.0 (/tmp/adder)
+";
+        assert_eq!(
+            normalize(raw, &Preserved::default()),
+            "   Compiling adder v0.1.0 (file:///projects/adder)\n"
+        );
+    }
+
+    #[test]
+    fn toolchain_paths_point_to_home_without_triple() {
+        let raw = "  --> /Users/carol/.rustup/toolchains/1.85.0-aarch64-apple-darwin/lib/rustlib/src/rust/library/core/src/option.rs:10:5";
+        let expected = "  --> file:///home/.rustup/toolchains/1.85/lib/rustlib/src/rust/library/core/src/option.rs:10:5";
+        assert_eq!(normalize(raw, &Preserved::default()), expected);
+
+        let linux = "   --> /home/runner/.rustup/toolchains/1.85-x86_64-unknown-linux-gnu/lib/rustlib/src/rust/library/std/src/io/mod.rs:1";
+        assert_eq!(
+            normalize(linux, &Preserved::default()),
+            "   --> file:///home/.rustup/toolchains/1.85/lib/rustlib/src/rust/library/std/src/io/mod.rs:1"
+        );
+    }
+
+    #[test]
+    fn miri_paths_are_normalized() {
+        let raw = "     Running `/Users/carol/.rustup/toolchains/nightly-aarch64-apple-darwin/bin/cargo-miri runner target/miri/aarch64-apple-darwin/debug/unsafe-example`";
+        let expected = "     Running `file:///home/.rustup/toolchains/nightly/bin/cargo-miri runner target/miri/debug/unsafe-example`";
+        assert_eq!(normalize(raw, &Preserved::default()), expected);
+    }
+
+    #[test]
+    fn preserves_values_from_previous_output() {
+        let previous = "$ cargo test
+   Compiling adder v0.1.0 (file:///projects/adder)
+    Finished `test` profile [unoptimized + debuginfo] target(s) in 0.57s
+     Running unittests src/lib.rs (target/debug/deps/adder-92948b65e88960b4)
+
+thread 'tests::another' (6019162) panicked at src/lib.rs:17:9:
+
+test result: FAILED. 1 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.00s
+";
+        let preserved = Preserved::from_output(previous);
+        assert_eq!(
+            preserved,
+            Preserved {
+                compile_time: Some(String::from("0.57")),
+                test_hash: Some(String::from("92948b65e88960b4")),
+                panic_pids: vec![String::from("6019162")],
+                test_times: vec![String::from("0.00")],
+            }
+        );
+
+        let raw = "   Compiling adder v0.1.0 (/tmp/adder)
+    Finished `test` profile [unoptimized + debuginfo] target(s) in 1.32s
+     Running unittests src/lib.rs (target/debug/deps/adder-0123456789abcdef)
+     Running tests/integration_test.rs (target/debug/deps/integration_test-fedcba9876543210)
+
+thread 'tests::another' (1234) panicked at src/lib.rs:17:9:
+
+test result: FAILED. 1 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.01s
+";
+        let expected = "   Compiling adder v0.1.0 (file:///projects/adder)
+    Finished `test` profile [unoptimized + debuginfo] target(s) in 0.57s
+     Running unittests src/lib.rs (target/debug/deps/adder-92948b65e88960b4)
+     Running tests/integration_test.rs (target/debug/deps/integration_test-92948b65e88960b4)
+
+thread 'tests::another' (6019162) panicked at src/lib.rs:17:9:
+
+test result: FAILED. 1 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.00s
+";
+        assert_eq!(normalize(raw, &preserved), expected);
+    }
+
+    #[test]
+    fn new_values_are_kept_when_there_is_nothing_to_preserve() {
+        let raw = "    Finished `dev` profile [unoptimized + debuginfo] target(s) in 1.32s";
+        assert_eq!(normalize(raw, &Preserved::default()), raw);
+    }
+
+    #[test]
+    fn extra_panics_reuse_the_last_preserved_id() {
+        let preserved = Preserved {
+            panic_pids: vec![String::from("100"), String::from("200")],
+            ..Preserved::default()
+        };
+        let raw = "thread 'main' (1) panicked at a
+thread 'other' (2) panicked at b
+thread 'third' (3) panicked at c";
+        let expected = "thread 'main' (100) panicked at a
+thread 'other' (200) panicked at b
+thread 'third' (200) panicked at c";
+        assert_eq!(normalize(raw, &preserved), expected);
+    }
+
+    #[test]
+    fn runs_commands_with_interleaved_output() {
+        let dir = std::env::temp_dir();
+        let env = BTreeMap::new();
+        let ran = run(&Step::new("cargo --version"), &dir, None, &env).unwrap();
+        assert!(ran.output.starts_with("cargo "));
+        assert_eq!(ran.outcome(), Outcome::Compiles);
+
+        assert!(run(&Step::new(""), &dir, None, &env).is_err());
+        assert!(
+            run(
+                &Step::new("not-a-real-command-for-the-book"),
+                &dir,
+                None,
+                &env
+            )
+            .is_err()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn runs_commands_with_input_and_environment() {
+        let dir = std::env::temp_dir();
+        let env = BTreeMap::from([(
+            String::from("BOOK_GREETING"),
+            String::from("hello"),
+        )]);
+        let cat = Step {
+            stdin: Some(String::from("typed in\n")),
+            ..Step::new("cat")
+        };
+        let ran = run(&cat, &dir, None, &env).unwrap();
+        assert_eq!(ran.output, "typed in\n");
+
+        let ran = run(&Step::new("printenv BOOK_GREETING"), &dir, None, &env)
+            .unwrap();
+        assert_eq!(ran.output, "hello\n");
+
+        let ran = run(&Step::new("cat"), &dir, None, &env).unwrap();
+        assert_eq!(ran.output, "");
+    }
+
+    /// A directory with a shell script in it to run, since commands can't be
+    /// given to `sh -c` when they are split on whitespace.
+    #[cfg(unix)]
+    fn script_dir(name: &str, script: &str) -> TempDir {
+        let dir = TempDir::new(&format!("output-{name}"));
+        fs::write(dir.join("script.sh"), script).unwrap();
+        dir
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn types_each_line_of_input_after_the_prompt() {
+        let dir = script_dir(
+            "prompt",
+            r#"
+echo "Guess:"
+read a
+echo "got $a"
+echo "Guess:"
+read b
+echo "got $b"
+echo "Guess:"
+read c || echo "no more input"
+"#,
+        );
+        let step = Step {
+            stdin: Some(String::from("1\n  2\n")),
+            prompt: Some(String::from("Guess:")),
+            ..Step::new("sh script.sh")
+        };
+        let ran = run(&step, &dir, None, &BTreeMap::new()).unwrap();
+        assert_eq!(
+            ran.output,
+            "Guess:\n1\ngot 1\nGuess:\n  2\ngot 2\nGuess:\nno more input\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stops_programs_which_keep_prompting_without_input() {
+        let dir = script_dir(
+            "prompt-forever",
+            r#"
+while true; do
+    echo "Guess:"
+    read a
+    echo "got $a"
+done
+"#,
+        );
+        let step = Step {
+            stdin: Some(String::from("1\n")),
+            prompt: Some(String::from("Guess:")),
+            ..Step::new("sh script.sh")
+        };
+        let ran = run(&step, &dir, None, &BTreeMap::new()).unwrap();
+        assert_eq!(ran.output, "Guess:\n1\ngot 1\nGuess:\n");
+    }
+
+    #[test]
+    fn keeps_what_random_programs_printed() {
+        let step = Step {
+            preserve: Some(String::from(r"secret: (\d+)")),
+            ..Step::new("cargo run")
+        };
+        let previous = "   Compiling game v0.1.0\n     Running `game`\n\
+                        Guess!\nsecret: 3\nToo big!\n";
+        let output = "   Compiling game v0.1.0\n    Finished in 0.1s\n     \
+                      Running `game`\nGuess!\nsecret: 7\nToo small!\n";
+        assert_eq!(
+            keep_random_output(&step, previous, output).unwrap(),
+            "   Compiling game v0.1.0\n    Finished in 0.1s\n     \
+             Running `game`\nGuess!\nsecret: 3\nToo big!\n"
+        );
+
+        // Without a previous value there is nothing to keep.
+        assert_eq!(keep_random_output(&step, "", output).unwrap(), output);
+        assert_eq!(
+            keep_random_output(&Step::new("cargo run"), previous, output)
+                .unwrap(),
+            output
+        );
+
+        // The output up to the random value has to look the same.
+        let changed = output.replace("Guess!", "Guess the number!");
+        assert!(keep_random_output(&step, previous, &changed).is_err());
+        let missing = output.replace("secret: 7\n", "");
+        assert!(keep_random_output(&step, previous, &missing).is_err());
+
+        let step = Step {
+            preserve: Some(String::from("secret: (")),
+            ..step
+        };
+        assert!(keep_random_output(&step, previous, output).is_err());
+    }
+
+    #[test]
+    fn only_listings_without_dependencies_share_target_dirs() {
+        let no_deps = r#"[package]
+name = "variables"
+version = "0.1.0"
+edition = "2024"
+
+[dependencies]
+"#;
+        assert!(can_share_target_dir(no_deps, "cargo run"));
+        assert!(!can_share_target_dir(no_deps, "cargo +nightly miri run"));
+
+        let deps = format!("{no_deps}rand = \"0.10.1\"\n");
+        assert!(!can_share_target_dir(&deps, "cargo run"));
+
+        let dev_deps = format!("{no_deps}\n[dev-dependencies]\nfoo = \"1\"\n");
+        assert!(!can_share_target_dir(&dev_deps, "cargo test"));
+
+        let workspace =
+            "[workspace]\nresolver = \"3\"\nmembers = [\"adder\"]\n";
+        assert!(!can_share_target_dir(workspace, "cargo build"));
+    }
+
+    #[test]
+    fn drift_is_a_unified_diff() {
+        let path = Path::new("listings/ch01/listing-01-01/output.txt");
+        let checked_in = "$ cargo run\nHello, world!\n";
+        assert_eq!(drift(path, checked_in, checked_in), None);
+
+        let diff = drift(path, checked_in, "$ cargo run\nHello, Ferris!\n")
+            .expect("output changed");
+        assert_eq!(
+            diff,
+            "--- a/listings/ch01/listing-01-01/output.txt
++++ b/listings/ch01/listing-01-01/output.txt
+@@ -1,2 +1,2 @@
+ $ cargo run
+-Hello, world!
++Hello, Ferris!
+"
+        );
+    }
+
+    #[test]
+    fn finds_the_package_name() {
+        let cargo_toml = r#"[package]
+name = "hello_cargo"
+version = "0.1.0"
+
+[dependencies]
+name = "not-the-package"
+"#;
+        assert_eq!(package_name(cargo_toml), Some("hello_cargo"));
+        assert_eq!(package_name("[workspace]\nmembers = []"), None);
+    }
+
+    #[test]
+    fn cache_round_trips() {
+        let mut cache = Cache::default();
+        cache.insert("ch03/no-listing-01", String::from("abc123"));
+        cache.insert("ch02/listing-02-01", String::from("def456"));
+
+        let dir = TempDir::new("output-cache");
+        let path = dir.join("cache");
+        cache.save(&path).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "def456 ch02/listing-02-01\nabc123 ch03/no-listing-01\n"
+        );
+        assert_eq!(Cache::load(&path).unwrap(), cache);
+        drop(dir);
+
+        assert!(cache.is_fresh("ch03/no-listing-01", "abc123"));
+        assert!(!cache.is_fresh("ch03/no-listing-01", "def456"));
+        assert!(!cache.is_fresh("ch04/listing-04-01", "abc123"));
+
+        assert_eq!(Cache::load(&path).unwrap(), Cache::default());
+    }
+
+    #[test]
+    fn listing_hash_depends_on_every_input() {
+        let hash =
+            listing_hash("src", "rustc 1.90", Some("trpl"), "$ cargo run");
+        assert_eq!(hash.len(), 64);
+        assert_eq!(
+            hash,
+            listing_hash("src", "rustc 1.90", Some("trpl"), "$ cargo run")
+        );
+        assert_ne!(
+            hash,
+            listing_hash("src2", "rustc 1.90", Some("trpl"), "$ cargo run")
+        );
+        assert_ne!(
+            hash,
+            listing_hash("src", "rustc 1.91", Some("trpl"), "$ cargo run")
+        );
+        assert_ne!(
+            hash,
+            listing_hash("src", "rustc 1.90", None, "$ cargo run")
+        );
+        assert_ne!(
+            hash,
+            listing_hash("src", "rustc 1.90", Some("trpl"), "$ cargo test")
+        );
+        // Parts cannot run into each other.
+        assert_ne!(
+            listing_hash("ab", "c", None, ""),
+            listing_hash("a", "bc", None, "")
+        );
+    }
+
+    #[test]
+    fn hash_dir_ignores_target_and_output_and_sees_changes() {
+        let dir = TempDir::new("output-hash");
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("src/main.rs"), "fn main() {}").unwrap();
+        let hash = hash_dir(&dir).unwrap();
+
+        fs::create_dir_all(dir.join("target/debug")).unwrap();
+        fs::write(dir.join("target/debug/main"), "binary").unwrap();
+        fs::write(dir.join("output.txt"), "$ cargo run").unwrap();
+        assert_eq!(hash_dir(&dir).unwrap(), hash);
+
+        fs::write(dir.join("src/main.rs"), "fn main() { }").unwrap();
+        assert_ne!(hash_dir(&dir).unwrap(), hash);
+    }
+}
@@ -0,0 +1,1161 @@
+//! Produce the archives of complete listing projects published with each
+//! release of the book.
+//!
+//! The listings are copied without the comments and files which only exist
+//! for the book's sake, and archived so that the same listings always produce
+//! byte-for-byte the same archive: entries are sorted, and every one gets the
+//! same modification time, owner, and permissions.
+//!
+//! Cleaning a listing could break it, so the cleaned listings can be checked
+//! with [`verify`] before they're archived.
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use toml_edit::{DocumentMut, TableLike};
+
+use crate::manifest::{self, Manifest, Outcome};
+use crate::output;
+
+/// The modification time of every entry in an archive: the start of 1980, the
+/// earliest time a zip archive can record.
+pub const MTIME: u64 = 315_532_800;
+
+/// The kinds of archive we can produce.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    TarGz,
+    Zip,
+}
+
+impl Format {
+    /// The format named on the command line, either `tar.gz` or `zip`.
+    pub fn from_name(name: &str) -> Result<Format, Box<dyn Error>> {
+        match name {
+            "tar.gz" | "tgz" => Ok(Format::TarGz),
+            "zip" => Ok(Format::Zip),
+            _ => Err(format!(
+                "Unknown archive format '{name}': expected tar.gz or zip"
+            )
+            .into()),
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::TarGz => "tar.gz",
+            Format::Zip => "zip",
+        }
+    }
+}
+
+/// Which listings to include in a release.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Filter {
+    /// Leave out the `output-only-*` listings, which only exist to produce
+    /// output shown in the book and aren't meant to be read on their own.
+    pub exclude_output_only: bool,
+}
+
+impl Filter {
+    pub fn includes(&self, listing_name: &str) -> bool {
+        !(self.exclude_output_only && listing_name.starts_with("output-only-"))
+    }
+}
+
+/// What to do with the listings' path dependencies on the `trpl` crate, which
+/// point into this repository and so don't work in a downloaded listing.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Trpl {
+    /// Leave them alone, for building the listings inside this repository.
+    Path,
+    /// Depend on this version of `trpl` from crates.io instead.
+    CratesIo(String),
+    /// Copy the `trpl` crate in this directory into each chapter which uses
+    /// it, and depend on that copy.
+    Bundle(PathBuf),
+}
+
+impl Trpl {
+    /// The way of dealing with `trpl` named on the command line: `path`,
+    /// `crates-io` for the version of the crate in `trpl_dir`, or `bundle`
+    /// for the crate in `trpl_dir`.
+    ///
+    /// With `crates-io`, that version must already be published, or the
+    /// listings won't build. This can't check crates.io, but it refuses when
+    /// the crate's `CHANGELOG.md` lists unreleased changes, because then the
+    /// crate in `trpl_dir` isn't the one published as that version.
+    pub fn from_name(
+        name: &str,
+        trpl_dir: &Path,
+    ) -> Result<Trpl, Box<dyn Error>> {
+        match name {
+            "path" => Ok(Trpl::Path),
+            "crates-io" => {
+                let path = trpl_dir.join("Cargo.toml");
+                let cargo_toml: toml::Table = fs::read_to_string(&path)
+                    .map_err(|e| {
+                        format!("Could not read '{}': {e}", path.display())
+                    })?
+                    .parse()?;
+                let version = cargo_toml
+                    .get("package")
+                    .and_then(|package| package.get("version"))
+                    .and_then(|version| version.as_str())
+                    .ok_or_else(|| {
+                        format!("No package version in '{}'", path.display())
+                    })?;
+                let changelog = trpl_dir.join("CHANGELOG.md");
+                if fs::read_to_string(&changelog)
+                    .is_ok_and(|changelog| has_unreleased_changes(&changelog))
+                {
+                    return Err(format!(
+                        "'{}' lists unreleased changes, so trpl {version} on \
+                        crates.io does not have them: publish a new version \
+                        first, or release the listings with --trpl=bundle",
+                        changelog.display()
+                    )
+                    .into());
+                }
+                Ok(Trpl::CratesIo(version.to_string()))
+            }
+            "bundle" => Ok(Trpl::Bundle(trpl_dir.to_path_buf())),
+            _ => Err(format!(
+                "Unknown way to release trpl '{name}': expected path, \
+                crates-io, or bundle"
+            )
+            .into()),
+        }
+    }
+
+    /// Rewrite the `trpl` path dependencies in a listing's `Cargo.toml`, if it
+    /// has any, for a listing `depth` directories below its chapter.
+    ///
+    /// They're found in every dependency section, whether written inline or
+    /// as a `[dependencies.trpl]` table, and anything else they set, like
+    /// features, is kept, as is the rest of the file.
+    pub fn rewrite(
+        &self,
+        cargo_toml: &str,
+        depth: usize,
+    ) -> Result<Option<String>, Box<dyn Error>> {
+        if *self == Trpl::Path {
+            return Ok(None);
+        }
+        let mut document: DocumentMut = cargo_toml.parse()?;
+        let mut rewritten = false;
+        for dependencies in dependency_tables(document.as_table_mut(), true) {
+            for (name, dependency) in dependencies.iter_mut() {
+                let Some(table) = dependency.as_table_like_mut() else {
+                    continue;
+                };
+                let package = table
+                    .get("package")
+                    .and_then(|package| package.as_str())
+                    .unwrap_or(name.get());
+                if package != "trpl" || !table.contains_key("path") {
+                    continue;
+                }
+                match self {
+                    Trpl::Path => unreachable!("path dependencies are kept"),
+                    Trpl::CratesIo(version) => {
+                        table.remove("path");
+                        table.insert("version", toml_edit::value(version));
+                        if let Some(inline) = dependency.as_inline_table_mut() {
+                            inline.sort_values_by(|a, _, b, _| {
+                                (b == "version").cmp(&(a == "version"))
+                            });
+                            // The space before `}` went with `path` if it
+                            // was last.
+                            inline.fmt();
+                            if inline.len() == 1 {
+                                *dependency = toml_edit::value(version);
+                            }
+                        }
+                    }
+                    Trpl::Bundle(_) => {
+                        let path = format!("{}trpl", "../".repeat(depth));
+                        table.insert("path", toml_edit::value(path));
+                    }
+                }
+                rewritten = true;
+            }
+        }
+        Ok(rewritten.then(|| document.to_string()))
+    }
+}
+
+/// Whether a changelog has entries under an "Unreleased" heading.
+fn has_unreleased_changes(changelog: &str) -> bool {
+    changelog
+        .lines()
+        .skip_while(|line| !line.eq_ignore_ascii_case("## unreleased"))
+        .skip(1)
+        .take_while(|line| !line.starts_with("## "))
+        .any(|line| !line.trim().is_empty())
+}
+
+/// The tables of dependencies in `table`, which is a whole `Cargo.toml` if
+/// `top_level` is set, or else one of its `[workspace]` or `[target.*]`
+/// sections.
+fn dependency_tables(
+    table: &mut dyn TableLike,
+    top_level: bool,
+) -> Vec<&mut dyn TableLike> {
+    let mut tables = Vec::new();
+    for (key, item) in table.iter_mut() {
+        match key.get() {
+            "dependencies" | "dev-dependencies" | "build-dependencies" => {
+                tables.extend(item.as_table_like_mut());
+            }
+            "workspace" if top_level => {
+                if let Some(workspace) = item.as_table_like_mut() {
+                    tables.extend(dependency_tables(workspace, false));
+                }
+            }
+            "target" if top_level => {
+                let targets = item.as_table_like_mut().into_iter();
+                for (_, target) in targets.flat_map(|t| t.iter_mut()) {
+                    if let Some(target) = target.as_table_like_mut() {
+                        tables.extend(dependency_tables(target, false));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    tables
+}
+
+/// Copy the cleaned version of every listing in `listings_dir` that `filter`
+/// includes into `out_dir`, which is emptied first, keeping the chapter
+/// directories, and dealing with their `trpl` dependencies as `trpl` says.
+/// Returns the names of the chapters, in order.
+pub fn clean_listings(
+    listings_dir: &Path,
+    out_dir: &Path,
+    filter: Filter,
+    trpl: &Trpl,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    if out_dir.is_dir() {
+        fs::remove_dir_all(out_dir)?;
+    }
+    fs::create_dir_all(out_dir).map_err(|e| {
+        format!("Could not create dir at '{}': {e}", out_dir.display())
+    })?;
+
+    let mut chapters = Vec::new();
+    for chapter_path in sorted_entries(listings_dir)? {
+        if !chapter_path.is_dir() {
+            eprintln!(
+                "'{}' is not a directory, skipping",
+                chapter_path.display()
+            );
+            continue;
+        }
+
+        let chapter_name = chapter_path
+            .file_name()
+            .expect("Chapter should've had a name");
+
+        // Create a corresponding chapter dir in the output directory
+        let output_chapter_path = out_dir.join(chapter_name);
+        fs::create_dir(&output_chapter_path).map_err(|e| {
+            format!(
+                "could not create dir at '{}': {e}",
+                output_chapter_path.display()
+            )
+        })?;
+
+        let mut uses_trpl = false;
+        for listing_path in sorted_entries(&chapter_path)? {
+            if !listing_path.is_dir() {
+                eprintln!(
+                    "'{}' is not a directory, skipping",
+                    listing_path.display(),
+                );
+                continue;
+            }
+
+            let listing_name = listing_path
+                .file_name()
+                .expect("Listing should've had a name");
+            if !filter.includes(&listing_name.to_string_lossy()) {
+                continue;
+            }
+
+            // Create a corresponding listing dir in the output chapter dir
+            let output_listing_dir = output_chapter_path.join(listing_name);
+            fs::create_dir(&output_listing_dir).map_err(|e| {
+                format!(
+                    "could not create dir '{}': {e}",
+                    output_listing_dir.display()
+                )
+            })?;
+
+            // Catch mistakes in the manifest before anything relies on it
+            Manifest::load(&listing_path)?;
+
+            // Copy all the cleaned files in the listing to the output dir
+            uses_trpl |= copy_cleaned_listing_files(
+                &listing_path,
+                &output_listing_dir,
+                trpl,
+                1,
+            )?;
+        }
+
+        if let Trpl::Bundle(trpl_dir) = trpl
+            && uses_trpl
+        {
+            let bundled = output_chapter_path.join("trpl");
+            fs::create_dir(&bundled)?;
+            copy_cleaned_listing_files(trpl_dir, &bundled, &Trpl::Path, 1)?;
+        }
+
+        chapters.push(chapter_name.to_string_lossy().into_owned());
+    }
+    Ok(chapters)
+}
+
+/// The paths of the entries in `dir`, sorted by name.
+fn sorted_entries(dir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut entries = fs::read_dir(dir)
+        .map_err(|e| format!("Could not read '{}': {e}", dir.display()))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("bad dir entry in {}: {e}", dir.display()))?;
+    entries.sort();
+    Ok(entries)
+}
+
+// Cleaned listings will not contain:
+//
+// - `target` directories
+// - `output.txt` files used to display output in the book
+// - `listing.toml` files describing how to build the listing for the book's tooling
+// - anchor comments or snip comments
+// - empty `main` functions in `lib.rs` files used to trick rustdoc
+// - `trpl` path dependencies, unless they are to be left alone
+//
+// Returns whether any of the files depended on `trpl`. `depth` is how far
+// below the chapter directory `to` is.
+fn copy_cleaned_listing_files(
+    from: &Path,
+    to: &Path,
+    trpl: &Trpl,
+    depth: usize,
+) -> Result<bool, Box<dyn Error>> {
+    let mut uses_trpl = false;
+    for item_path in sorted_entries(from)? {
+        let item_name =
+            item_path.file_name().expect("Item should've had a name");
+        let output_item = to.join(item_name);
+
+        if item_path.is_dir() {
+            // Don't copy `target` directories
+            if item_name != "target" {
+                fs::create_dir(&output_item).map_err(|e| {
+                    format!(
+                        "Could not create output directory '{}': {e}",
+                        output_item.display()
+                    )
+                })?;
+                uses_trpl |= copy_cleaned_listing_files(
+                    &item_path,
+                    &output_item,
+                    trpl,
+                    depth + 1,
+                )?;
+            }
+        } else {
+            // Don't copy output files or the manifests used by the book's tooling
+            if item_name != "output.txt" && item_name != manifest::FILE_NAME {
+                if item_path.extension().is_some_and(|ext| ext == "rs") {
+                    let source =
+                        fs::read_to_string(&item_path).map_err(|e| {
+                            format!(
+                                "Could not read '{}': {e}",
+                                item_path.display()
+                            )
+                        })?;
+                    let is_lib = item_name == "lib.rs";
+                    fs::write(&output_item, clean_rust(&source, is_lib))?;
+                } else if item_name == "Cargo.toml"
+                    && let Some(rewritten) =
+                        trpl.rewrite(&fs::read_to_string(&item_path)?, depth)?
+                {
+                    uses_trpl = true;
+                    fs::write(&output_item, rewritten)?;
+                } else {
+                    // Copy any non-Rust files without modification
+                    fs::copy(&item_path, &output_item).map_err(|e| {
+                        format!(
+                            "Could not copy from '{}' to '{}': {e}",
+                            item_path.display(),
+                            output_item.display()
+                        )
+                    })?;
+                }
+            }
+        }
+    }
+
+    Ok(uses_trpl)
+}
+
+lazy_static! {
+    static ref ANCHOR_OR_SNIP_COMMENTS: Regex = Regex::new(
+        r"(?x)
+    //\s*ANCHOR:\s*[\w_-]+      # Remove all anchor comments
+    |
+    //\s*ANCHOR_END:\s*[\w_-]+  # Remove all anchor ending comments
+    |
+    //\s*--snip--               # Remove all snip comments
+    "
+    )
+    .unwrap();
+}
+
+lazy_static! {
+    static ref EMPTY_MAIN: Regex = Regex::new(r"fn main\(\) \{}").unwrap();
+}
+
+/// Remove the lines of a Rust file which are only there for the book:
+///
+/// - anchor comments or snip comments
+/// - empty `main` functions in `lib.rs` files used to trick rustdoc
+pub fn clean_rust(source: &str, is_lib: bool) -> String {
+    let mut cleaned = String::new();
+    for line in source.lines() {
+        if !ANCHOR_OR_SNIP_COMMENTS.is_match(line)
+            && (!is_lib || !EMPTY_MAIN.is_match(line))
+        {
+            cleaned.push_str(line);
+            cleaned.push('\n');
+        }
+    }
+    cleaned
+}
+
+lazy_static! {
+    static ref DOES_NOT_COMPILE: Regex = Regex::new(
+        r"(?m)^```[^\n]*does_not_compile[^\n]*\n\{\{#(?:rustdoc_)?include \.\./listings/([^/]+/[^/]+)/"
+    )
+    .unwrap();
+}
+
+/// The listings shown in the book's Markdown in `src_dir` in a code block
+/// marked `does_not_compile`, by their path relative to the listings
+/// directory, like `ch04-understanding-ownership/listing-04-06`.
+pub fn does_not_compile_in_book(
+    src_dir: &Path,
+) -> Result<HashSet<String>, Box<dyn Error>> {
+    let mut listings = HashSet::new();
+    for path in sorted_entries(src_dir)? {
+        if path.extension().is_none_or(|ext| ext != "md") {
+            continue;
+        }
+        let text = fs::read_to_string(&path)
+            .map_err(|e| format!("Could not read '{}': {e}", path.display()))?;
+        listings.extend(
+            DOES_NOT_COMPILE
+                .captures_iter(&text)
+                .map(|caps| caps[1].to_string()),
+        );
+    }
+    Ok(listings)
+}
+
+/// Whether a listing should compile, going by what its manifest says, or else
+/// by its `output.txt` and whether the book says it doesn't.
+pub fn expected_to_compile(
+    manifest: &Manifest,
+    output: Option<&str>,
+    does_not_compile_in_book: bool,
+) -> bool {
+    match manifest.outcome {
+        Some(outcome) => outcome != Outcome::Fails,
+        None => {
+            !does_not_compile_in_book
+                && output.is_none_or(|output| {
+                    !output.contains("error: could not compile")
+                })
+        }
+    }
+}
+
+/// The path dependencies in a cleaned listing's `Cargo.toml` which won't work
+/// once the listing is downloaded on its own, because they point to somewhere
+/// outside `root`, the directory of released listings, or to nothing at all.
+pub fn unusable_path_dependencies(
+    cargo_toml: &str,
+    project_dir: &Path,
+    root: &Path,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let table: toml::Table = cargo_toml.parse()?;
+    let root = normalize_path(&std::path::absolute(root)?);
+    let project_dir = std::path::absolute(project_dir)?;
+
+    // Dependencies can be listed for the package, for the workspace, and for
+    // particular targets.
+    let mut sections = vec![&table];
+    sections.extend(table.get("workspace").and_then(|w| w.as_table()));
+    if let Some(targets) = table.get("target").and_then(|t| t.as_table()) {
+        sections.extend(targets.values().filter_map(|t| t.as_table()));
+    }
+    let dependencies = sections.into_iter().flat_map(|section| {
+        ["dependencies", "dev-dependencies", "build-dependencies"]
+            .into_iter()
+            .filter_map(|key| section.get(key).and_then(|deps| deps.as_table()))
+            .flatten()
+    });
+
+    let mut unusable = Vec::new();
+    for (name, dependency) in dependencies {
+        let Some(path) = dependency.get("path").and_then(|p| p.as_str()) else {
+            continue;
+        };
+        let resolved = normalize_path(&project_dir.join(path));
+        if !resolved.starts_with(&root) {
+            unusable.push(format!(
+                "dependency `{name}` at `{path}` is outside the released \
+                listings"
+            ));
+        } else if !resolved.join("Cargo.toml").is_file() {
+            unusable.push(format!(
+                "dependency `{name}` at `{path}` does not exist"
+            ));
+        }
+    }
+    Ok(unusable)
+}
+
+/// Resolve the `.` and `..` components of `path` without looking at the file
+/// system, which may not have anything there.
+pub(crate) fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// The directories with a `Cargo.toml` in a listing: usually the listing
+/// itself, but listings of workspaces and of several crates have them one
+/// level down.
+fn projects(listing_dir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    if listing_dir.join("Cargo.toml").is_file() {
+        return Ok(vec![listing_dir.to_path_buf()]);
+    }
+    Ok(sorted_entries(listing_dir)?
+        .into_iter()
+        .filter(|dir| dir.join("Cargo.toml").is_file())
+        .collect())
+}
+
+/// A cleaned listing which won't work for someone who downloads it.
+#[derive(Debug, PartialEq)]
+pub struct Breakage {
+    /// The project's path relative to the cleaned listings.
+    pub project: String,
+    pub problem: String,
+}
+
+/// Check that the cleaned listings in `out_dir` still work on their own: that
+/// their path dependencies can be found, and that the ones which should
+/// compile still do, going by the original listings in `listings_dir` and the
+/// `does_not_compile` listings in the book.
+///
+/// Each project is checked with `cargo check --offline`, with its artifacts in
+/// `target_dir`. Returns how many projects were checked and what broke.
+pub fn verify(
+    listings_dir: &Path,
+    out_dir: &Path,
+    target_dir: &Path,
+    does_not_compile: &HashSet<String>,
+) -> Result<(usize, Vec<Breakage>), Box<dyn Error>> {
+    let target_dir = std::path::absolute(target_dir)?;
+    // How many packages of each name have been checked, so that packages with
+    // the same name get different target directories. Cargo can mistake one
+    // for the other being up to date otherwise.
+    let mut checked_names: HashMap<String, usize> = HashMap::new();
+    let mut checked = 0;
+    let mut breakages = Vec::new();
+
+    for chapter_dir in sorted_entries(out_dir)? {
+        if !chapter_dir.is_dir() {
+            continue;
+        }
+        for listing_dir in sorted_entries(&chapter_dir)? {
+            let listing = listing_dir
+                .strip_prefix(out_dir)?
+                .to_string_lossy()
+                .replace('\\', "/");
+
+            for project in projects(&listing_dir)? {
+                let name = project
+                    .strip_prefix(out_dir)?
+                    .to_string_lossy()
+                    .replace('\\', "/");
+                // Projects inside a listing have their own manifest and output.
+                let original = listings_dir.join(&name);
+                let manifest = Manifest::load(&original)?;
+                let output =
+                    fs::read_to_string(original.join("output.txt")).ok();
+                if !expected_to_compile(
+                    &manifest,
+                    output.as_deref(),
+                    does_not_compile.contains(&listing),
+                ) {
+                    continue;
+                }
+
+                let cargo_toml =
+                    fs::read_to_string(project.join("Cargo.toml"))?;
+                checked += 1;
+
+                let unusable =
+                    unusable_path_dependencies(&cargo_toml, &project, out_dir)?;
+                if !unusable.is_empty() {
+                    for problem in unusable {
+                        breakages.push(Breakage {
+                            project: name.clone(),
+                            problem,
+                        });
+                    }
+                    continue;
+                }
+
+                let package = output::package_name(&cargo_toml)
+                    .unwrap_or("workspace")
+                    .to_string();
+                let count = checked_names.entry(package.clone()).or_default();
+                *count += 1;
+                let output = Command::new("cargo")
+                    .args(["check", "--offline", "--quiet"])
+                    .current_dir(&project)
+                    .env(
+                        "CARGO_TARGET_DIR",
+                        target_dir.join(&package).join(count.to_string()),
+                    )
+                    .env("RUSTFLAGS", output::RUSTFLAGS)
+                    .output()
+                    .map_err(|e| format!("Could not run cargo check: {e}"))?;
+                if !output.status.success() {
+                    breakages.push(Breakage {
+                        project: name,
+                        problem: format!(
+                            "cargo check failed:\n{}",
+                            String::from_utf8_lossy(&output.stderr).trim_end()
+                        ),
+                    });
+                }
+            }
+        }
+    }
+    Ok((checked, breakages))
+}
+
+/// Write everything in `dir` to a new archive at `archive`, under a top-level
+/// directory named `root`.
+pub fn write_archive(
+    dir: &Path,
+    root: &str,
+    archive: &Path,
+    format: Format,
+) -> Result<(), Box<dyn Error>> {
+    if let Some(parent) = archive.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let file = File::create(archive).map_err(|e| {
+        format!("Could not create '{}': {e}", archive.display())
+    })?;
+
+    let mut entries = vec![(root.to_string(), None)];
+    for entry in walkdir::WalkDir::new(dir).min_depth(1).sort_by_file_name() {
+        let entry = entry?;
+        let relative = entry
+            .path()
+            .strip_prefix(dir)?
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let name = format!("{root}/{relative}");
+        let contents = if entry.file_type().is_dir() {
+            None
+        } else {
+            Some(fs::read(entry.path())?)
+        };
+        entries.push((name, contents));
+    }
+
+    match format {
+        Format::TarGz => write_tar_gz(file, &entries)?,
+        Format::Zip => write_zip(file, &entries)?,
+    }
+    Ok(())
+}
+
+/// An entry in an archive: its path and, for files, its contents.
+type Entry = (String, Option<Vec<u8>>);
+
+fn write_tar_gz(file: File, entries: &[Entry]) -> io::Result<()> {
+    // The gzip header has no timestamp or file name unless one is set.
+    let encoder = flate2::write::GzEncoder::new(
+        BufWriter::new(file),
+        flate2::Compression::default(),
+    );
+    let mut archive = tar::Builder::new(encoder);
+    for (name, contents) in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_mtime(MTIME);
+        header.set_uid(0);
+        header.set_gid(0);
+        match contents {
+            Some(contents) => {
+                header.set_entry_type(tar::EntryType::Regular);
+                header.set_mode(0o644);
+                header.set_size(contents.len() as u64);
+                archive.append_data(&mut header, name, contents.as_slice())?;
+            }
+            None => {
+                header.set_entry_type(tar::EntryType::Directory);
+                header.set_mode(0o755);
+                header.set_size(0);
+                archive.append_data(
+                    &mut header,
+                    format!("{name}/"),
+                    io::empty(),
+                )?;
+            }
+        }
+    }
+    archive.into_inner()?.finish()?.flush()
+}
+
+fn write_zip(file: File, entries: &[Entry]) -> zip::result::ZipResult<()> {
+    let mut archive = zip::ZipWriter::new(BufWriter::new(file));
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .last_modified_time(zip::DateTime::default());
+    for (name, contents) in entries {
+        match contents {
+            Some(contents) => {
+                archive.start_file(name, options.unix_permissions(0o644))?;
+                archive.write_all(contents)?;
+            }
+            None => {
+                archive.add_directory(name, options.unix_permissions(0o755))?;
+            }
+        }
+    }
+    archive.finish()?.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    use std::io::Read;
+
+    #[test]
+    fn cleaning_removes_anchors_snips_and_empty_main_in_libs() {
+        let source = "// ANCHOR: here
+pub fn add(left: u64, right: u64) -> u64 {
+    // --snip--
+    left + right
+}
+// ANCHOR_END: here
+fn main() {}
+";
+        let cleaned = "pub fn add(left: u64, right: u64) -> u64 {
+    left + right
+}
+";
+        assert_eq!(clean_rust(source, true), cleaned);
+        assert_eq!(
+            clean_rust(source, false),
+            format!("{cleaned}fn main() {{}}\n")
+        );
+    }
+
+    #[test]
+    fn output_only_listings_can_be_excluded() {
+        assert!(Filter::default().includes("output-only-01-add"));
+        let filter = Filter {
+            exclude_output_only: true,
+        };
+        assert!(!filter.includes("output-only-01-add"));
+        assert!(filter.includes("listing-01-01"));
+    }
+
+    #[test]
+    fn knows_formats_by_name() {
+        assert_eq!(Format::from_name("tar.gz").unwrap(), Format::TarGz);
+        assert_eq!(Format::from_name("zip").unwrap().extension(), "zip");
+        assert!(Format::from_name("rar").is_err());
+    }
+
+    #[test]
+    fn finds_listings_the_book_says_do_not_compile() {
+        let src = TempDir::new("release-src");
+        fs::write(
+            src.join("ch04-01-what-is-ownership.md"),
+            "```rust,ignore,does_not_compile
+{{#rustdoc_include ../listings/ch04-understanding-ownership/listing-04-06/src/main.rs:here}}
+```
+
+```rust
+{{#rustdoc_include ../listings/ch04-understanding-ownership/listing-04-07/src/main.rs}}
+```
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            does_not_compile_in_book(&src).unwrap(),
+            HashSet::from([String::from(
+                "ch04-understanding-ownership/listing-04-06"
+            )])
+        );
+    }
+
+    #[test]
+    fn works_out_which_listings_should_compile() {
+        let failing = "error[E0382]: borrow of moved value: `s1`\n\
+                       error: could not compile `ownership`";
+        let manifest = Manifest::default();
+        assert!(expected_to_compile(&manifest, None, false));
+        assert!(expected_to_compile(&manifest, Some("Hello, world!"), false));
+        assert!(!expected_to_compile(&manifest, Some(failing), false));
+        assert!(!expected_to_compile(&manifest, None, true));
+
+        let panics = Manifest {
+            outcome: Some(Outcome::Panics),
+            ..Manifest::default()
+        };
+        assert!(expected_to_compile(&panics, Some(failing), true));
+        let fails = Manifest {
+            outcome: Some(Outcome::Fails),
+            ..Manifest::default()
+        };
+        assert!(!expected_to_compile(&fails, None, false));
+    }
+
+    #[test]
+    fn finds_path_dependencies_outside_the_release() {
+        let root = TempDir::new("release-deps");
+        let project =
+            root.join("ch14-more-about-cargo/listing-14-07/add/adder");
+        fs::create_dir_all(&project).unwrap();
+        let add_one =
+            root.join("ch14-more-about-cargo/listing-14-07/add/add_one");
+        fs::create_dir_all(&add_one).unwrap();
+        fs::write(add_one.join("Cargo.toml"), "[package]\n").unwrap();
+
+        let cargo_toml = r#"
+[package]
+name = "adder"
+
+[dependencies]
+add_one = { path = "../add_one" }
+rand = "0.8.5"
+
+[dev-dependencies]
+trpl = { path = "../../../../../packages/trpl" }
+
+[target.'cfg(unix)'.dependencies]
+missing = { path = "./missing" }
+"#;
+        assert_eq!(
+            unusable_path_dependencies(cargo_toml, &project, &root).unwrap(),
+            vec![
+                "dependency `trpl` at `../../../../../packages/trpl` is \
+                 outside the released listings",
+                "dependency `missing` at `./missing` does not exist",
+            ]
+        );
+        assert!(
+            unusable_path_dependencies("[package", &project, &root).is_err()
+        );
+    }
+
+    #[test]
+    fn rewrites_trpl_path_dependencies() {
+        let cargo_toml = "[dependencies]\n\
+                          trpl = { path = \"../../../packages/trpl\" }\n";
+        assert_eq!(Trpl::Path.rewrite(cargo_toml, 1).unwrap(), None);
+        assert_eq!(
+            Trpl::CratesIo(String::from("0.3.0"))
+                .rewrite(cargo_toml, 1)
+                .unwrap(),
+            Some(String::from("[dependencies]\ntrpl = \"0.3.0\"\n"))
+        );
+        assert_eq!(
+            Trpl::Bundle(PathBuf::from("packages/trpl"))
+                .rewrite(cargo_toml, 2)
+                .unwrap(),
+            Some(String::from(
+                "[dependencies]\ntrpl = { path = \"../../trpl\" }\n"
+            ))
+        );
+        assert_eq!(
+            Trpl::CratesIo(String::from("0.3.0"))
+                .rewrite("[dependencies]\nrand = \"0.8.5\"\n", 1)
+                .unwrap(),
+            None
+        );
+        assert!(
+            Trpl::CratesIo(String::from("0.3.0"))
+                .rewrite("[dependencies", 1)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn rewrites_trpl_path_dependencies_in_any_form() {
+        let cargo_toml = "\
+[package]
+name = \"async_await\"
+
+[dependencies]
+# Keeps its comment.
+trpl = { path = \"../../../packages/trpl\", features = [\"serde\"] }
+rand = \"0.8.5\"
+
+[dev-dependencies.trpl]
+version = \"0.3.0\"
+path = \"../../../packages/trpl\"
+
+[target.'cfg(unix)'.build-dependencies]
+support = { package = \"trpl\", path = \"../../../packages/trpl\" }
+
+[workspace.dependencies]
+trpl = { path = \"../../packages/trpl\" }
+";
+        assert_eq!(
+            Trpl::CratesIo(String::from("0.4.0"))
+                .rewrite(cargo_toml, 1)
+                .unwrap()
+                .unwrap(),
+            "\
+[package]
+name = \"async_await\"
+
+[dependencies]
+# Keeps its comment.
+trpl = { version = \"0.4.0\", features = [\"serde\"] }
+rand = \"0.8.5\"
+
+[dev-dependencies.trpl]
+version = \"0.4.0\"
+
+[target.'cfg(unix)'.build-dependencies]
+support = { version = \"0.4.0\", package = \"trpl\" }
+
+[workspace.dependencies]
+trpl = \"0.4.0\"
+"
+        );
+        assert_eq!(
+            Trpl::Bundle(PathBuf::from("packages/trpl"))
+                .rewrite(cargo_toml, 1)
+                .unwrap()
+                .unwrap(),
+            cargo_toml
+                .replace("../../../packages/", "../")
+                .replace("../../packages/trpl", "../trpl")
+        );
+    }
+
+    #[test]
+    fn knows_ways_to_release_trpl_by_name() {
+        let trpl_dir = TempDir::new("release-trpl");
+        fs::write(
+            trpl_dir.join("Cargo.toml"),
+            "[package]\nname = \"trpl\"\nversion = \"0.3.0\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(Trpl::from_name("path", &trpl_dir).unwrap(), Trpl::Path);
+        assert_eq!(
+            Trpl::from_name("crates-io", &trpl_dir).unwrap(),
+            Trpl::CratesIo(String::from("0.3.0"))
+        );
+        assert_eq!(
+            Trpl::from_name("bundle", &trpl_dir).unwrap(),
+            Trpl::Bundle(trpl_dir.to_path_buf())
+        );
+        assert!(Trpl::from_name("git", &trpl_dir).is_err());
+        assert!(
+            Trpl::from_name("crates-io", &trpl_dir.join("missing")).is_err()
+        );
+
+        fs::write(
+            trpl_dir.join("CHANGELOG.md"),
+            "# CHANGELOG\n\n## Unreleased\n\n## 0.3.0\n\n- Changes.\n",
+        )
+        .unwrap();
+        assert!(Trpl::from_name("crates-io", &trpl_dir).is_ok());
+        fs::write(
+            trpl_dir.join("CHANGELOG.md"),
+            "# CHANGELOG\n\n## Unreleased\n\n- New!\n\n## 0.3.0\n",
+        )
+        .unwrap();
+        assert!(Trpl::from_name("crates-io", &trpl_dir).is_err());
+        assert!(Trpl::from_name("bundle", &trpl_dir).is_ok());
+    }
+
+    #[test]
+    fn bundles_trpl_with_the_chapters_that_use_it() {
+        let root = TempDir::new("release-bundle");
+        let listings = listings(&root);
+        let listing = listings.join("ch17-async-await/listing-17-01");
+        fs::create_dir_all(&listing).unwrap();
+        fs::write(
+            listing.join("Cargo.toml"),
+            "[dependencies]\ntrpl = { path = \"../../../packages/trpl\" }\n",
+        )
+        .unwrap();
+        let trpl_dir = root.join("packages/trpl");
+        fs::create_dir_all(trpl_dir.join("src")).unwrap();
+        fs::create_dir_all(trpl_dir.join("target")).unwrap();
+        fs::write(trpl_dir.join("Cargo.toml"), "[package]\n").unwrap();
+        fs::write(trpl_dir.join("src/lib.rs"), "pub fn run() {}\n").unwrap();
+        let out = root.join("out");
+
+        clean_listings(
+            &listings,
+            &out,
+            Filter::default(),
+            &Trpl::Bundle(trpl_dir),
+        )
+        .unwrap();
+        assert_eq!(
+            fs::read_to_string(
+                out.join("ch17-async-await/listing-17-01/Cargo.toml")
+            )
+            .unwrap(),
+            "[dependencies]\ntrpl = { path = \"../trpl\" }\n"
+        );
+        let bundled = out.join("ch17-async-await/trpl");
+        assert!(bundled.join("src/lib.rs").exists());
+        assert!(!bundled.join("target").exists());
+        assert!(!out.join("ch01-getting-started/trpl").exists());
+        assert!(
+            unusable_path_dependencies(
+                &fs::read_to_string(
+                    out.join("ch17-async-await/listing-17-01/Cargo.toml")
+                )
+                .unwrap(),
+                &out.join("ch17-async-await/listing-17-01"),
+                &out,
+            )
+            .unwrap()
+            .is_empty()
+        );
+    }
+
+    fn listings(root: &Path) -> PathBuf {
+        let listings = root.join("listings");
+        let listing = listings.join("ch01-getting-started/listing-01-01");
+        fs::create_dir_all(listing.join("src")).unwrap();
+        fs::create_dir_all(listing.join("target/debug")).unwrap();
+        fs::write(listing.join("Cargo.toml"), "[package]\n").unwrap();
+        fs::write(
+            listing.join("src/main.rs"),
+            "// ANCHOR: all\nfn main() {}\n// ANCHOR_END: all\n",
+        )
+        .unwrap();
+        fs::write(listing.join("output.txt"), "$ cargo run\n").unwrap();
+        fs::write(listing.join("target/debug/main"), "binary").unwrap();
+        let output_only = listings.join("ch01-getting-started/output-only-01");
+        fs::create_dir_all(&output_only).unwrap();
+        fs::write(output_only.join("Cargo.toml"), "[package]\n").unwrap();
+        listings
+    }
+
+    #[test]
+    fn cleans_listings_and_archives_them_reproducibly() {
+        let root = TempDir::new("release");
+        let listings = listings(&root);
+        let out = root.join("out");
+
+        let filter = Filter {
+            exclude_output_only: true,
+        };
+        let chapters =
+            clean_listings(&listings, &out, filter, &Trpl::Path).unwrap();
+        assert_eq!(chapters, vec!["ch01-getting-started"]);
+        let listing = out.join("ch01-getting-started/listing-01-01");
+        assert_eq!(
+            fs::read_to_string(listing.join("src/main.rs")).unwrap(),
+            "fn main() {}\n"
+        );
+        assert!(!listing.join("output.txt").exists());
+        assert!(!listing.join("target").exists());
+        assert!(!out.join("ch01-getting-started/output-only-01").exists());
+
+        for format in [Format::TarGz, Format::Zip] {
+            let first = root.join(format!("first.{}", format.extension()));
+            let second = root.join(format!("second.{}", format.extension()));
+            write_archive(&out, "listings", &first, format).unwrap();
+            // Files which are touched but not changed don't change the archive.
+            fs::write(listing.join("Cargo.toml"), "[package]\n").unwrap();
+            write_archive(&out, "listings", &second, format).unwrap();
+            assert_eq!(fs::read(&first).unwrap(), fs::read(&second).unwrap());
+        }
+
+        let tar_gz = File::open(root.join("first.tar.gz")).unwrap();
+        let mut archive =
+            tar::Archive::new(flate2::read::GzDecoder::new(tar_gz));
+        let entries: Vec<_> = archive
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let entry = entry.unwrap();
+                let header = entry.header();
+                assert_eq!(header.mtime().unwrap(), MTIME);
+                assert_eq!(header.uid().unwrap(), 0);
+                entry.path().unwrap().display().to_string()
+            })
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                "listings/",
+                "listings/ch01-getting-started/",
+                "listings/ch01-getting-started/listing-01-01/",
+                "listings/ch01-getting-started/listing-01-01/Cargo.toml",
+                "listings/ch01-getting-started/listing-01-01/src/",
+                "listings/ch01-getting-started/listing-01-01/src/main.rs",
+            ]
+        );
+
+        let zip = File::open(root.join("first.zip")).unwrap();
+        let mut archive = zip::ZipArchive::new(zip).unwrap();
+        let mut main = String::new();
+        archive
+            .by_name("listings/ch01-getting-started/listing-01-01/src/main.rs")
+            .unwrap()
+            .read_to_string(&mut main)
+            .unwrap();
+        assert_eq!(main, "fn main() {}\n");
+    }
+}
@@ -0,0 +1,35 @@
+use regex::{Captures, Regex};
+
+/// Remove the markup only the online version of the book needs: the info
+/// strings on code fences, and the `<span>`s around filenames and captions.
+pub fn remove_markup(input: &str) -> String {
+    let filename_regex =
+        Regex::new(r#"\A<span class="filename">(.*)</span>\z"#).unwrap();
+    // Captions sometimes take up multiple lines.
+    let caption_start_regex =
+        Regex::new(r#"\A<span class="caption">(.*)\z"#).unwrap();
+    let caption_end_regex = Regex::new(r#"(.*)</span>\z"#).unwrap();
+    let regexen = [filename_regex, caption_start_regex, caption_end_regex];
+
+    let lines: Vec<_> = input
+        .lines()
+        .flat_map(|line| {
+            // Remove our syntax highlighting and rustdoc markers.
+            if line.starts_with("```") {
+                Some(String::from("```"))
+            // Remove the span around filenames and captions.
+            } else {
+                let result =
+                    regexen.iter().fold(line.to_string(), |result, regex| {
+                        regex
+                            .replace_all(&result, |caps: &Captures<'_>| {
+                                caps.get(1).unwrap().as_str().to_string()
+                            })
+                            .to_string()
+                    });
+                Some(result)
+            }
+        })
+        .collect();
+    lines.join("\n")
+}
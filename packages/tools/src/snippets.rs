@@ -0,0 +1,561 @@
+//! Check that the snippets the book shows from the listings are what it means
+//! to show.
+//!
+//! The book shows part of a listing with
+//! `{{#rustdoc_include ../listings/.../src/main.rs:here}}`, which mdBook
+//! resolves to the lines between `// ANCHOR: here` and `// ANCHOR_END: here`
+//! in that file. mdBook silently shows nothing for an anchor it can't find, so
+//! this checks that:
+//!
+//! - every anchor an include refers to exists
+//! - every `ANCHOR` has exactly one matching `ANCHOR_END`, and every
+//!   `ANCHOR_END` has an `ANCHOR` before it
+//! - every `// --snip--` comment is indented like the code around it, which is
+//!   easy to get wrong because rustfmt leaves comments alone
+//!
+//! An anchor can mark several regions of a file, which mdBook joins together;
+//! that's how a snippet leaves out code in the middle, usually replacing it
+//! with a `// --snip--` comment between the regions.
+//!
+//! It also reports anchors no include refers to, as warnings, since they are
+//! usually left over from text that was rewritten.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::lint::{Finding, Level};
+use crate::release::normalize_path;
+
+lazy_static! {
+    static ref INCLUDE: Regex = Regex::new(
+        r"(\\)?\{\{#(?:rustdoc_)?include\s+([^}:\s]+)(?::([^}\s]*))?[^}]*\}\}"
+    )
+    .unwrap();
+    // What mdBook treats as a range of lines rather than an anchor name.
+    static ref LINE_RANGE: Regex = Regex::new(r"^\d*(:\d*)?$").unwrap();
+    // The same patterns mdBook uses to find anchors.
+    static ref ANCHOR_START: Regex =
+        Regex::new(r"ANCHOR:\s*([\w_-]+)").unwrap();
+    static ref ANCHOR_END: Regex =
+        Regex::new(r"ANCHOR_END:\s*([\w_-]+)").unwrap();
+    static ref SNIP: Regex = Regex::new(r"^(\s*)//\s*-- ?snip ?--").unwrap();
+}
+
+/// A place where a page of the book includes (part of) a file.
+#[derive(Debug, PartialEq)]
+pub struct Include {
+    /// 1-based line number in the page.
+    pub line_num: usize,
+    /// The included file, relative to the same directory as the page's path.
+    pub file: PathBuf,
+    /// The anchor naming the part of the file to include, if the include
+    /// names one rather than a range of lines or nothing at all.
+    pub anchor: Option<String>,
+}
+
+/// The files included by `page`, whose text is `text`.
+pub fn includes(page: &Path, text: &str) -> Vec<Include> {
+    let dir = page.parent().unwrap_or(Path::new(""));
+    text.lines()
+        .enumerate()
+        .flat_map(|(i, line)| {
+            INCLUDE
+                .captures_iter(line)
+                // `\{{#include ...}}` is how mdBook shows an include literally.
+                .filter(|caps| caps.get(1).is_none())
+                .map(move |caps| Include {
+                    line_num: i + 1,
+                    file: normalize_path(&dir.join(&caps[2])),
+                    anchor: caps
+                        .get(3)
+                        .map(|anchor| anchor.as_str())
+                        .filter(|anchor| !LINE_RANGE.is_match(anchor))
+                        .map(String::from),
+                })
+        })
+        .collect()
+}
+
+/// The anchors in `source`, by name, with the line each one starts on, along
+/// with any anchors which are started again before they end, never ended, or
+/// ended without being started.
+pub fn anchors(source: &str) -> (BTreeMap<String, usize>, Vec<Finding>) {
+    let mut defined = BTreeMap::new();
+    let mut open: BTreeMap<String, usize> = BTreeMap::new();
+    let mut findings = Vec::new();
+
+    for (i, line) in source.lines().enumerate() {
+        let line_num = i + 1;
+        if let Some(caps) = ANCHOR_END.captures(line) {
+            let name = &caps[1];
+            if open.remove(name).is_none() {
+                findings.push(Finding {
+                    line_num,
+                    message: format!(
+                        "`ANCHOR_END: {name}` without an `ANCHOR: {name}` \
+                         before it"
+                    ),
+                });
+            }
+        } else if let Some(caps) = ANCHOR_START.captures(line) {
+            let name = caps[1].to_string();
+            if let Some(started) = open.get(&name) {
+                findings.push(Finding {
+                    line_num,
+                    message: format!(
+                        "anchor `{name}` started on line {started} hasn't \
+                         ended yet"
+                    ),
+                });
+            } else {
+                defined.entry(name.clone()).or_insert(line_num);
+                open.insert(name, line_num);
+            }
+        }
+    }
+
+    for (name, line_num) in open {
+        findings.push(Finding {
+            line_num,
+            message: format!("anchor `{name}` has no `ANCHOR_END: {name}`"),
+        });
+    }
+    findings.sort_by_key(|finding| finding.line_num);
+
+    (defined, findings)
+}
+
+/// The `// --snip--` comments in `source` which aren't indented like the code
+/// around them: either like the line before, or one level in from it if it
+/// opens a block, or like the line after, which is what rustfmt does with a
+/// comment just before a closing brace. Anchor comments don't count as code,
+/// since the book doesn't show them.
+pub fn misindented_snips(source: &str) -> Vec<Finding> {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut findings = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let Some(caps) = SNIP.captures(line) else {
+            continue;
+        };
+        let indent = caps[1].len();
+
+        let before =
+            lines[..i]
+                .iter()
+                .rev()
+                .find(|line| is_code(line))
+                .map(|line| {
+                    let opens = line.trim_end().ends_with(['{', '(', '[']);
+                    indentation(line) + if opens { 4 } else { 0 }
+                });
+        let after = lines[i + 1..]
+            .iter()
+            .find(|line| is_code(line))
+            .map(|line| indentation(line));
+        let Some(expected) = before.or(after) else {
+            continue;
+        };
+        if before != Some(indent) && after != Some(indent) {
+            findings.push(Finding {
+                line_num: i + 1,
+                message: format!(
+                    "`--snip--` is indented {indent} spaces, but the code \
+                     around it is indented {expected}"
+                ),
+            });
+        }
+    }
+    findings
+}
+
+fn is_code(line: &str) -> bool {
+    !line.trim().is_empty()
+        && !ANCHOR_START.is_match(line)
+        && !ANCHOR_END.is_match(line)
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Something wrong with a snippet, at a line of a page or listing file.
+#[derive(Debug, PartialEq)]
+pub struct Problem {
+    pub path: PathBuf,
+    /// 1-based line number.
+    pub line_num: usize,
+    /// [`Level::Warn`] for unused anchors, which don't break anything, and
+    /// [`Level::Deny`] for everything else.
+    pub level: Level,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}: {}",
+            self.path.display(),
+            self.line_num,
+            self.level,
+            self.message
+        )
+    }
+}
+
+/// Everything wrong with the snippets the pages in `src_dir` include from the
+/// files in `listings_dir`.
+#[derive(Debug, Default)]
+pub struct Report {
+    /// How many includes of an anchor were checked.
+    pub includes: usize,
+    /// How many listing files were checked.
+    pub files: usize,
+    pub problems: Vec<Problem>,
+}
+
+impl Report {
+    /// Check the includes in every page in `src_dir` and every file in
+    /// `listings_dir`.
+    pub fn new(
+        src_dir: &Path,
+        listings_dir: &Path,
+    ) -> Result<Report, Box<dyn Error>> {
+        let mut report = Report::default();
+
+        // Which anchors of which files the pages use, and where.
+        let mut used: BTreeMap<
+            PathBuf,
+            BTreeMap<String, Vec<(PathBuf, usize)>>,
+        > = BTreeMap::new();
+        for page in files(src_dir, "md")? {
+            let text = read(&page)?;
+            for include in includes(&page, &text) {
+                let Some(anchor) = include.anchor else {
+                    continue;
+                };
+                report.includes += 1;
+                used.entry(include.file)
+                    .or_default()
+                    .entry(anchor)
+                    .or_default()
+                    .push((page.clone(), include.line_num));
+            }
+        }
+
+        // Included files outside the listings still need their anchors
+        // checked, but not their unused anchors, since they may be included
+        // from elsewhere.
+        let listing_files: BTreeSet<PathBuf> = files(listings_dir, "")?
+            .into_iter()
+            .map(|path| normalize_path(&path))
+            .collect();
+        let mut to_check = listing_files.clone();
+        to_check.extend(used.keys().cloned());
+
+        for path in to_check {
+            let includes = used.get(&path);
+            if !path.is_file() {
+                for (anchor, places) in includes.into_iter().flatten() {
+                    report.deny_each(places, || {
+                        format!(
+                            "`{}` (for anchor `{anchor}`) does not exist",
+                            path.display()
+                        )
+                    });
+                }
+                continue;
+            }
+            // Images and other binary files can't have anchors.
+            let Ok(source) = fs::read_to_string(&path) else {
+                continue;
+            };
+            report.files += 1;
+
+            let (defined, findings) = anchors(&source);
+            let mut findings = findings;
+            if path.extension().is_some_and(|ext| ext == "rs") {
+                findings.extend(misindented_snips(&source));
+            }
+            for finding in findings {
+                report.problems.push(Problem {
+                    path: path.clone(),
+                    line_num: finding.line_num,
+                    level: Level::Deny,
+                    message: finding.message,
+                });
+            }
+
+            for (anchor, places) in includes.into_iter().flatten() {
+                if !defined.contains_key(anchor) {
+                    report.deny_each(places, || {
+                        format!("`{}` has no anchor `{anchor}`", path.display())
+                    });
+                }
+            }
+            if listing_files.contains(&path) {
+                for (anchor, &line_num) in &defined {
+                    if includes
+                        .is_none_or(|includes| !includes.contains_key(anchor))
+                    {
+                        report.problems.push(Problem {
+                            path: path.clone(),
+                            line_num,
+                            level: Level::Warn,
+                            message: format!(
+                                "anchor `{anchor}` is not included anywhere"
+                            ),
+                        });
+                    }
+                }
+            }
+        }
+
+        report
+            .problems
+            .sort_by(|a, b| (&a.path, a.line_num).cmp(&(&b.path, b.line_num)));
+        Ok(report)
+    }
+
+    fn deny_each(
+        &mut self,
+        places: &[(PathBuf, usize)],
+        message: impl Fn() -> String,
+    ) {
+        for (page, line_num) in places {
+            self.problems.push(Problem {
+                path: page.clone(),
+                line_num: *line_num,
+                level: Level::Deny,
+                message: message(),
+            });
+        }
+    }
+
+    /// Whether any of the problems should fail the check.
+    pub fn failed(&self) -> bool {
+        self.problems
+            .iter()
+            .any(|problem| problem.level == Level::Deny)
+    }
+}
+
+/// The files under `dir` with the extension `ext`, or every file if `ext` is
+/// empty, leaving out build output.
+fn files(dir: &Path, ext: &str) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut files = Vec::new();
+    for entry in walkdir::WalkDir::new(dir)
+        .min_depth(1)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| entry.file_name() != "target")
+    {
+        let entry = entry
+            .map_err(|e| format!("Could not read '{}': {e}", dir.display()))?;
+        let path = entry.path();
+        if entry.file_type().is_file()
+            && (ext.is_empty() || path.extension().is_some_and(|e| e == ext))
+        {
+            files.push(path.to_path_buf());
+        }
+    }
+    Ok(files)
+}
+
+fn read(path: &Path) -> Result<String, Box<dyn Error>> {
+    fs::read_to_string(path)
+        .map_err(|e| format!("Could not read '{}': {e}", path.display()).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn finds_includes_of_anchors() {
+        let page = Path::new("src/ch01-01-installation.md");
+        let text = "\
+{{#rustdoc_include ../listings/ch01/listing-01-01/src/main.rs:here}}
+{{#include ../listings/ch01/listing-01-01/Cargo.toml:6:8}}
+{{#include ../listings/ch01/listing-01-01/output.txt}}
+Shown literally: \\{{#include file.rs:here}}
+{{#rustdoc_include ../listings/ch10/no-listing-10/src/main.rs:1st}}
+";
+        assert_eq!(
+            includes(page, text),
+            vec![
+                Include {
+                    line_num: 1,
+                    file: PathBuf::from(
+                        "listings/ch01/listing-01-01/src/main.rs"
+                    ),
+                    anchor: Some(String::from("here")),
+                },
+                Include {
+                    line_num: 2,
+                    file: PathBuf::from(
+                        "listings/ch01/listing-01-01/Cargo.toml"
+                    ),
+                    anchor: None,
+                },
+                Include {
+                    line_num: 3,
+                    file: PathBuf::from(
+                        "listings/ch01/listing-01-01/output.txt"
+                    ),
+                    anchor: None,
+                },
+                Include {
+                    line_num: 5,
+                    file: PathBuf::from(
+                        "listings/ch10/no-listing-10/src/main.rs"
+                    ),
+                    anchor: Some(String::from("1st")),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_mismatched_and_unterminated_anchors() {
+        let source = "\
+// ANCHOR: all
+// ANCHOR: here
+fn main() {}
+// ANCHOR_END: here
+// ANCHOR_END: there
+// ANCHOR: here
+// ANCHOR: here
+// ANCHOR_END: here
+";
+        let (defined, findings) = anchors(source);
+        assert_eq!(
+            defined,
+            BTreeMap::from([
+                (String::from("all"), 1),
+                (String::from("here"), 2)
+            ])
+        );
+        assert_eq!(
+            findings,
+            vec![
+                Finding {
+                    line_num: 1,
+                    message: String::from(
+                        "anchor `all` has no `ANCHOR_END: all`"
+                    ),
+                },
+                Finding {
+                    line_num: 5,
+                    message: String::from(
+                        "`ANCHOR_END: there` without an `ANCHOR: there` \
+                         before it"
+                    ),
+                },
+                Finding {
+                    line_num: 7,
+                    message: String::from(
+                        "anchor `here` started on line 6 hasn't ended yet"
+                    ),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn snips_are_indented_like_the_code_around_them() {
+        let source = "\
+fn main() {
+    // --snip--
+    let x = 5;
+    // --snip--
+}
+
+fn other() {
+    match x {
+        1 => {}
+        // --snip--
+    }
+    // ANCHOR_END: here
+        // --snip--
+}
+// --snip--
+";
+        assert_eq!(
+            misindented_snips(source),
+            vec![Finding {
+                line_num: 13,
+                message: String::from(
+                    "`--snip--` is indented 8 spaces, but the code around it \
+                     is indented 4"
+                ),
+            }]
+        );
+    }
+
+    #[test]
+    fn checks_pages_against_listings() {
+        let root = TempDir::new("snippets");
+        let src = root.join("src");
+        let listing = root.join("listings/ch01/listing-01-01");
+        fs::create_dir_all(&src).unwrap();
+        fs::create_dir_all(listing.join("src")).unwrap();
+        fs::create_dir_all(listing.join("target")).unwrap();
+        fs::write(
+            src.join("ch01-01-installation.md"),
+            "{{#rustdoc_include ../listings/ch01/listing-01-01/src/main.rs:here}}
+{{#rustdoc_include ../listings/ch01/listing-01-01/src/main.rs:there}}
+{{#rustdoc_include ../listings/ch01/listing-01-02/src/main.rs:here}}
+",
+        )
+        .unwrap();
+        fs::write(
+            listing.join("src/main.rs"),
+            "// ANCHOR: all
+// ANCHOR: here
+fn main() {}
+// ANCHOR_END: here
+// ANCHOR_END: all
+",
+        )
+        .unwrap();
+        fs::write(listing.join("target/main.rs"), "// ANCHOR: broken\n")
+            .unwrap();
+
+        let report = Report::new(&src, &root.join("listings")).unwrap();
+        assert_eq!(report.includes, 3);
+        assert_eq!(report.files, 1);
+        let problems: Vec<_> =
+            report.problems.iter().map(|p| p.to_string()).collect();
+        let page = src.join("ch01-01-installation.md");
+        let main = listing.join("src/main.rs");
+        assert_eq!(
+            problems,
+            vec![
+                format!(
+                    "{}:1: warn: anchor `all` is not included anywhere",
+                    main.display()
+                ),
+                format!(
+                    "{}:2: deny: `{}` has no anchor `there`",
+                    page.display(),
+                    main.display()
+                ),
+                format!(
+                    "{}:3: deny: `{}` (for anchor `here`) does not exist",
+                    page.display(),
+                    root.join("listings/ch01/listing-01-02/src/main.rs")
+                        .display()
+                ),
+            ]
+        );
+        assert!(report.failed());
+    }
+}
@@ -0,0 +1,51 @@
+//! Helpers shared by the unit tests.
+
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A fresh, empty directory for one test to work in, which is removed when it
+/// is dropped, so a test which fails part way through doesn't leave files
+/// behind for the next run to trip over.
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Create a directory named after `name` and this process in the system's
+    /// temporary directory, removing anything already there.
+    pub(crate) fn new(name: &str) -> TempDir {
+        // Keep directories apart even when one test makes several.
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "rust-book-{name}-{}-{}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::SeqCst)
+        ));
+        if path.exists() {
+            fs::remove_dir_all(&path).unwrap();
+        }
+        fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        // Nothing to do about a directory which can't be removed.
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,78 @@
+//! Tests which use the library functions together, the way the print export
+//! and other tooling do.
+
+use rust_book_tools::{
+    ConcatOptions, Summary, concat_chapters, convert_quotes,
+    find_straight_quotes, link2print, remove_hidden_lines, remove_links,
+    remove_markup,
+};
+
+/// Once `link2print` has run, URLs are just prose, so quotes have to be
+/// converted first.
+#[test]
+fn quotes_are_converted_before_links_are_printed() {
+    let input = r#"See ["Hello"](https://example.com/it's) for more."#;
+    let output = link2print(&convert_quotes(input));
+    assert_eq!(
+        output,
+        "See “Hello” at *https://example.com/it's* for more."
+    );
+}
+
+#[test]
+fn converting_quotes_twice_is_a_no_op() {
+    let input = r#"It's "done", isn't it?"#;
+    let once = convert_quotes(input);
+    assert_eq!(convert_quotes(&once), once);
+    assert!(find_straight_quotes(&once).is_empty());
+}
+
+#[test]
+fn cleaned_code_blocks_keep_their_quotes() {
+    let input = r#"Don't panic:
+
+```rust,ignore
+# fn main() {
+let s = "it's";
+# }
+```
+"#;
+    let output = convert_quotes(&remove_hidden_lines(&remove_markup(input)));
+    assert_eq!(
+        output,
+        r#"Don’t panic:
+
+```
+let s = "it's";
+```"#
+    );
+}
+
+#[test]
+fn concatenates_processed_sections() {
+    let first = remove_links(
+        "[Cargo](https://doc.rust-lang.org/cargo/)<!-- ignore -->",
+    );
+    let second = link2print("See [Cargo][cargo].\n[cargo]: https://crates.io");
+
+    let summary = Summary::parse(
+        "- [Getting Started](ch01-00-getting-started.md)
+  - [Hello, Cargo!](ch01-03-hello-cargo.md)",
+    )
+    .unwrap();
+    let chapters = concat_chapters(
+        &summary,
+        &ConcatOptions::default(),
+        [
+            ("ch01-03-hello-cargo.md", second.as_str()),
+            ("ch01-00-getting-started.md", first.as_str()),
+        ],
+    )
+    .unwrap();
+
+    let (name, chapter) = &chapters.files[0];
+    assert_eq!(name, "chapter01.md");
+    let start = chapter.find("Cargo\n").unwrap();
+    let next = chapter.find("See Cargo at *https://crates.io*.").unwrap();
+    assert!(start < next, "sections should be in summary order");
+}
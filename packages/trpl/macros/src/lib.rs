@@ -0,0 +1,110 @@
+//! The attribute macros for [`trpl`][trpl], a support crate for [_The Rust
+//! Programming Language_][book]. Use them through `trpl`, as `#[trpl::main]`
+//! and `#[trpl::test]`, rather than depending on this crate directly.
+//!
+//! [trpl]: https://docs.rs/trpl
+//! [book]: https://doc.rust-lang.org/book
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{ItemFn, parse_macro_input, spanned::Spanned};
+
+/// Let `main` be an `async fn`, by running its body with `trpl::block_on`.
+///
+/// ```ignore
+/// #[trpl::main]
+/// async fn main() {
+///     trpl::sleep(std::time::Duration::from_millis(1)).await;
+///     println!("Done!");
+/// }
+/// ```
+///
+/// is the same as
+///
+/// ```ignore
+/// fn main() {
+///     trpl::block_on(async {
+///         trpl::sleep(std::time::Duration::from_millis(1)).await;
+///         println!("Done!");
+///     })
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn main(args: TokenStream, item: TokenStream) -> TokenStream {
+    let function = parse_macro_input!(item as ItemFn);
+    expand("main", args, function, None)
+}
+
+/// Let a test be an `async fn`, by marking it `#[test]` and running its body
+/// with `trpl::block_on`.
+///
+/// ```ignore
+/// #[trpl::test]
+/// async fn sleeps() {
+///     trpl::sleep(std::time::Duration::from_millis(1)).await;
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn test(args: TokenStream, item: TokenStream) -> TokenStream {
+    let function = parse_macro_input!(item as ItemFn);
+    expand(
+        "test",
+        args,
+        function,
+        Some(quote!(#[::core::prelude::v1::test])),
+    )
+}
+
+fn expand(
+    name: &str,
+    args: TokenStream,
+    mut function: ItemFn,
+    attribute: Option<proc_macro2::TokenStream>,
+) -> TokenStream {
+    let args = proc_macro2::TokenStream::from(args);
+    let error = if !args.is_empty() {
+        Some(syn::Error::new(
+            args.span(),
+            format!("`#[trpl::{name}]` does not take any arguments"),
+        ))
+    } else if function.sig.asyncness.is_none() {
+        Some(syn::Error::new(
+            function.sig.fn_token.span(),
+            format!(
+                "`#[trpl::{name}]` only works on an `async fn`; add `async` \
+                 before `fn`"
+            ),
+        ))
+    } else if !function.sig.inputs.is_empty() {
+        Some(syn::Error::new(
+            function.sig.inputs.span(),
+            format!(
+                "`#[trpl::{name}]` only works on a function without arguments"
+            ),
+        ))
+    } else {
+        None
+    };
+    // Keep the function as it was alongside the error, so there are no more
+    // errors from it seeming to be missing.
+    if let Some(error) = error {
+        let error = error.into_compile_error();
+        return quote!(#error #function).into();
+    }
+
+    function.sig.asyncness = None;
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = function;
+    quote! {
+        #attribute
+        #(#attrs)*
+        #vis #sig {
+            ::trpl::block_on(async move #block)
+        }
+    }
+    .into()
+}
@@ -15,15 +15,30 @@
 //!    release at some point.
 
 // For direct use within the `trpl` crate, *not* re-exported.
-use std::{future::Future, pin::pin};
+use std::{
+    cell::Cell,
+    fmt,
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    pin::{Pin, pin},
+    sync::OnceLock,
+    task::{Context, Poll, ready},
+    time::Duration,
+};
 
 use futures::future;
 
+#[cfg(feature = "test-server")]
+pub mod test_server;
+
 // Re-exports, to be used like `trpl::join`.
 pub use futures::{
     future::{Either, join, join_all, join3},
     join,
 };
+// The types `Response` hands back, so you can name them without depending on
+// `reqwest` yourself.
+pub use reqwest::{StatusCode, Url, header::HeaderMap};
 pub use tokio::{
     fs::read_to_string,
     runtime::Runtime,
@@ -38,23 +53,44 @@ pub use tokio::{
     // The book collapses these differences for pedagogical simplicity, so that
     // readers are not asking why `unbounded` is now important and can focus on
     // the more important differences between sync and async APIs.
+    //
+    // For the same reason, the bounded channel gets the names `std` uses for
+    // its bounded channel, even though sending on it is `async` here.
     sync::mpsc::{
+        Receiver as SyncReceiver, Sender as SyncSender,
         UnboundedReceiver as Receiver, UnboundedSender as Sender,
-        unbounded_channel as channel,
+        channel as sync_channel, unbounded_channel as channel,
+    },
+    sync::{
+        broadcast::{
+            Receiver as BroadcastReceiver, Sender as BroadcastSender,
+            channel as broadcast,
+        },
+        oneshot::{
+            Receiver as OneshotReceiver, Sender as OneshotSender,
+            channel as oneshot,
+        },
     },
     task::{JoinHandle, spawn as spawn_task, yield_now},
-    time::{interval, sleep},
+    time::{error::Elapsed, interval, sleep},
 };
+pub use trpl_macros::{main, test};
 
 pub use tokio_stream::{
     Stream, StreamExt, iter as stream_from_iter,
-    wrappers::{IntervalStream, UnboundedReceiverStream as ReceiverStream},
+    wrappers::{
+        BroadcastStream, IntervalStream, ReceiverStream as SyncReceiverStream,
+        UnboundedReceiverStream as ReceiverStream,
+        errors::BroadcastStreamRecvError,
+    },
 };
 
-/// Run a single future to completion on a bespoke Tokio `Runtime`.
+/// Run a single future to completion on a shared Tokio `Runtime`.
 ///
-/// Every time you call this, a new instance of `tokio::runtime::Runtime` will
-/// be created (see the implementation for details: it is trivial). This is:
+/// The first time you call this, it creates a multi-threaded
+/// `tokio::runtime::Runtime`, and every call after that reuses it, so calling
+/// this in a loop or from many tests does not start a new pool of threads each
+/// time. This is:
 ///
 /// - Reasonable for teaching purposes, in that you do not generally need to set
 ///   up more than one runtime anyway, and especially do not in basic code like
@@ -62,9 +98,103 @@ pub use tokio_stream::{
 ///
 /// - Not *that* far off from what Tokio itself does under the hood in its own
 ///   `tokio::main` macro for supporting `async fn main`.
+///
+/// Since the runtime outlives any one call, tasks started with [`spawn_task`]
+/// which have not finished when the future does keep running in the
+/// background, until the program exits.
+///
+/// # Panics
+///
+/// Panics if called from inside a future which is already running on a
+/// runtime, e.g. inside an `async` block passed to `block_on` or
+/// [`spawn_task`]. Use `.await` there instead. Code the runtime runs on a
+/// separate thread for blocking, like a `tokio::task::spawn_blocking` closure,
+/// can call it.
 pub fn block_on<F: Future>(future: F) -> F::Output {
-    let rt = Runtime::new().unwrap();
-    rt.block_on(future)
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+    let runtime = RUNTIME.get_or_init(|| Runtime::new().unwrap());
+    block_on_with("block_on", runtime, future)
+}
+
+/// Run a single future to completion on a new single-threaded Tokio `Runtime`.
+///
+/// Unlike [`block_on`], everything the future does, including any tasks it
+/// starts with [`spawn_task`], runs on the current thread, taking turns only
+/// at `.await` points. That makes the order in which concurrent work
+/// interleaves the same every time you run it, which is handy for seeing how
+/// async code takes turns. Tasks which have not finished when the future does
+/// are canceled.
+///
+/// # Panics
+///
+/// Panics if called from inside a future which is already running on a
+/// runtime, just like [`block_on`].
+pub fn block_on_current_thread<F: Future>(future: F) -> F::Output {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    block_on_with("block_on_current_thread", &runtime, future)
+}
+
+/// Run a single future to completion on a new single-threaded Tokio `Runtime`
+/// whose clock starts out paused. Only available with the `test-util` feature,
+/// and meant for tests.
+///
+/// While the clock is paused, time only moves forward when you call
+/// [`advance`], or when every task is waiting on a timer, like [`sleep`] or
+/// [`interval`]: then the clock jumps straight to the next timer. That means
+/// code which sleeps for seconds finishes instantly, and always sees the same
+/// amount of time pass, so tests of it are fast and deterministic. Call
+/// [`resume`] to let the clock run normally again.
+///
+/// # Panics
+///
+/// Panics if called from inside a future which is already running on a
+/// runtime, just like [`block_on`].
+#[cfg(feature = "test-util")]
+pub fn block_on_paused<F: Future>(future: F) -> F::Output {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .start_paused(true)
+        .build()
+        .unwrap();
+    block_on_with("block_on_paused", &runtime, future)
+}
+
+// For controlling the paused clock in `block_on_paused`.
+#[cfg(feature = "test-util")]
+pub use tokio::time::{advance, pause, resume};
+
+/// Run `future` on `runtime` for the `trpl` function called `function`.
+///
+/// Only Tokio knows whether this thread is driving a runtime, where blocking is
+/// not allowed, rather than, say, running a [`tokio::task::spawn_blocking`]
+/// closure, where it is. So let it decide, but replace its panic, which is
+/// about what *it* cannot do and not very helpful when you did not call Tokio
+/// yourself. Tokio refuses before polling the future at all, which tells its
+/// panic apart from any panic in the future itself.
+fn block_on_with<F: Future>(
+    function: &str,
+    runtime: &Runtime,
+    future: F,
+) -> F::Output {
+    let started = Cell::new(false);
+    let future = async {
+        started.set(true);
+        future.await
+    };
+    match panic::catch_unwind(AssertUnwindSafe(|| runtime.block_on(future))) {
+        Ok(output) => output,
+        Err(_) if !started.get() => panic!(
+            "`trpl::{function}` was called from inside a future which is \
+             already being run by `trpl::block_on` or another async runtime. \
+             Blocking here would stop that future from making progress; use \
+             `.await` instead."
+        ),
+        Err(payload) => panic::resume_unwind(payload),
+    }
 }
 
 /// This function has been renamed to `block_on`; please see its documentation.
@@ -91,6 +221,9 @@ pub fn run<F: Future>(future: F) -> F::Output {
 /// - It internally *pins* the futures.
 /// - It throws away (rather than returning) the unused future (which is why it
 ///   can get away with pinning them).
+///
+/// For more than two futures, see [`select_all`] and the [`select!`] macro,
+/// which make the same simplification.
 pub async fn select<A, B, F1, F2>(f1: F1, f2: F2) -> Either<A, B>
 where
     F1: Future<Output = A>,
@@ -115,12 +248,249 @@ where
     select(f1, f2).await
 }
 
+/// Run any number of futures, taking whichever finishes first and canceling
+/// the rest, like [`select()`] does for two. Returns the position of the future
+/// which finished first, counting from 0, along with its output.
+///
+/// All the futures must be of the same type; see [`boxed`] for how to use
+/// futures of different types. If more than one is ready at once, the first
+/// one in the list wins. To select over a few futures with different outputs,
+/// use the [`select!`] macro instead.
+///
+/// # Panics
+///
+/// Panics if there are no futures, since then none of them can finish first.
+pub async fn select_all<I>(futures: I) -> (usize, <I::Item as Future>::Output)
+where
+    I: IntoIterator,
+    I::Item: Future,
+{
+    let futures: Vec<_> = futures.into_iter().map(Box::pin).collect();
+    let (output, index, _rest) = future::select_all(futures).await;
+    (index, output)
+}
+
+/// Run several futures, taking whichever finishes first and canceling the
+/// others, and then run the code for that future's branch with its output.
+///
+/// Each branch is written `pattern = future => expression`. The futures can
+/// have different output types, unlike with [`select_all`], and the result of
+/// the whole `select!` is the result of whichever branch's expression runs, so
+/// those must all have the same type. Like [`select()`], which it is built on,
+/// this drops the futures that lose; and if more than one future is ready at
+/// once, the first branch listed wins.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// let message = trpl::block_on(async {
+///     trpl::select! {
+///         n = async { trpl::sleep(Duration::from_millis(100)).await; 1 } =>
+///             format!("number {n}"),
+///         text = async { "hi" } => format!("text {text}"),
+///         () = trpl::sleep(Duration::from_millis(50)) => String::from("timeout"),
+///     }
+/// });
+/// assert_eq!(message, "text hi");
+/// ```
+///
+/// This can only be used inside an `async` block or function.
+#[macro_export]
+macro_rules! select {
+    ($($pat:pat = $future:expr => $body:expr),+ $(,)?) => {
+        $crate::select!(@match $crate::select!(@race $($future),+).await;
+            $($pat => $body),+)
+    };
+
+    // Nest the futures with `select`, so the first one is on the left and the
+    // rest are on the right...
+    (@race $future:expr) => {
+        $future
+    };
+    (@race $future:expr, $($rest:expr),+) => {
+        $crate::select($future, $crate::select!(@race $($rest),+))
+    };
+
+    // ...and take the `Either`s that gives apart the same way.
+    (@match $value:expr; $pat:pat => $body:expr) => {
+        match $value {
+            $pat => $body,
+        }
+    };
+    (@match $value:expr; $pat:pat => $body:expr, $($rest:tt)+) => {
+        match $value {
+            $crate::Either::Left($pat) => $body,
+            $crate::Either::Right(rest) => $crate::select!(@match rest; $($rest)+),
+        }
+    };
+}
+
+/// A future which has been put in a [`Box`] with [`boxed`], so that it has
+/// the same type as every other boxed future with the same output.
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + 'a>>;
+
+/// Put a future in a [`Box`] and pin it, so it can go in a collection with
+/// other futures of different types which have the same output.
+///
+/// Every `async` block has its own type, even if it looks just like another,
+/// so [`join_all`] and [`select_all`] cannot take a `Vec` of different ones.
+/// Boxing them gets around that, which is exactly what we do by hand in the
+/// book when we write out `Pin<Box<dyn Future<Output = ()>>>`:
+///
+/// ```
+/// let greetings = trpl::block_on(async {
+///     let hello = async { String::from("Hello") };
+///     let name = String::from("world");
+///     let world = async move { name };
+///     trpl::join_all(vec![trpl::boxed(hello), trpl::boxed(world)]).await
+/// });
+/// assert_eq!(greetings, vec!["Hello", "world"]);
+/// ```
+///
+/// Boxed futures are still futures, so they also work with [`join!`] and
+/// [`join()`].
+pub fn boxed<'a, F>(future: F) -> BoxFuture<'a, F::Output>
+where
+    F: Future + 'a,
+{
+    Box::pin(future)
+}
+
+/// Run a future, giving up on it if it does not finish within `duration`.
+///
+/// This is the same idea as the `timeout` function we build ourselves from
+/// [`select()`] and [`sleep`] in the book, ready-made: it returns `Ok` with the
+/// future's output if it finishes in time, and otherwise drops the future and
+/// returns [`Elapsed`].
+///
+/// Unlike Tokio's own `timeout`, which this is built on, this does not start
+/// the clock until it is first awaited, so it is fine to pass it straight to
+/// [`block_on`].
+pub async fn timeout<F: Future>(
+    duration: Duration,
+    future: F,
+) -> Result<F::Output, Elapsed> {
+    tokio::time::timeout(duration, future).await
+}
+
+/// A [`Stream`] of the one value sent on a [`oneshot`] channel, to go with
+/// [`ReceiverStream`], [`SyncReceiverStream`], and [`BroadcastStream`].
+///
+/// A [`OneshotReceiver`] is already a future, which is usually the easiest way
+/// to use it. Wrapping it in a stream lets you merge it with other streams, for
+/// example to stop a stream of messages when a shutdown signal arrives. The
+/// stream produces the value once, if it is sent, and then ends. If the sender
+/// is dropped without sending anything, the stream ends without producing
+/// anything at all.
+#[derive(Debug)]
+pub struct OneshotStream<T> {
+    receiver: Option<OneshotReceiver<T>>,
+}
+
+impl<T> OneshotStream<T> {
+    /// Wrap a [`OneshotReceiver`] in a stream.
+    pub fn new(receiver: OneshotReceiver<T>) -> OneshotStream<T> {
+        OneshotStream {
+            receiver: Some(receiver),
+        }
+    }
+}
+
+impl<T> Stream for OneshotStream<T> {
+    type Item = T;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<T>> {
+        let Some(receiver) = self.receiver.as_mut() else {
+            return Poll::Ready(None);
+        };
+        let result = ready!(Pin::new(receiver).poll(cx));
+        self.receiver = None;
+        Poll::Ready(result.ok())
+    }
+}
+
+impl<T> From<OneshotReceiver<T>> for OneshotStream<T> {
+    fn from(receiver: OneshotReceiver<T>) -> OneshotStream<T> {
+        OneshotStream::new(receiver)
+    }
+}
+
 /// Fetch data from a URL. For more convenient use in _The Rust Programming
 /// Language_, panics instead of returning a [`Result`] if the request fails.
 pub async fn get(url: &str) -> Response {
     Response(reqwest::get(url).await.unwrap())
 }
 
+/// Fetch data from a URL, returning an [`Error`] instead of panicking if the
+/// request fails.
+///
+/// Unlike [`get`], this treats a response with an error status code, like
+/// `404 Not Found`, as a failure: it returns [`Error::Status`].
+pub async fn try_get(url: &str) -> Result<Response, Error> {
+    error_for_status(reqwest::get(url).await.map_err(Error::Network)?)
+}
+
+fn error_for_status(response: reqwest::Response) -> Result<Response, Error> {
+    let status = response.status();
+    if status.is_client_error() || status.is_server_error() {
+        return Err(Error::Status(status));
+    }
+    Ok(Response(response))
+}
+
+/// The ways the fallible versions of `trpl`'s functions, like [`try_get`], can
+/// fail.
+#[derive(Debug)]
+pub enum Error {
+    /// The request could not be made, or the connection failed partway
+    /// through.
+    Network(reqwest::Error),
+    /// The server responded, but with an error status code.
+    Status(StatusCode),
+    /// The response body could not be read in full or decoded, e.g. because
+    /// the connection closed before all of it arrived, or it was not valid
+    /// JSON.
+    Decode(reqwest::Error),
+    /// A string was not a valid CSS selector. This holds the reason.
+    Selector(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Network(error) => write!(f, "network error: {error}"),
+            Error::Status(status) => write!(f, "error status: {status}"),
+            Error::Decode(error) => {
+                write!(f, "could not decode response: {error}")
+            }
+            Error::Selector(reason) => write!(f, "invalid selector: {reason}"),
+        }
+    }
+}
+
+impl Error {
+    /// Whether the request failed because it took longer than the timeout set
+    /// with [`ClientBuilder::timeout`] or [`RequestBuilder::timeout`].
+    pub fn is_timeout(&self) -> bool {
+        match self {
+            Error::Network(error) | Error::Decode(error) => error.is_timeout(),
+            Error::Status(_) | Error::Selector(_) => false,
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Network(error) | Error::Decode(error) => Some(error),
+            Error::Status(_) | Error::Selector(_) => None,
+        }
+    }
+}
+
 /// A thin wrapper around [`reqwest::Response`] to make the demos in _The Rust
 /// Programming Language_ substantially nicer to use.
 pub struct Response(reqwest::Response);
@@ -133,6 +503,192 @@ impl Response {
     pub async fn text(self) -> String {
         self.0.text().await.unwrap()
     }
+
+    /// Get the full response text, returning [`Error::Decode`] instead of
+    /// panicking if the body cannot be read or decoded.
+    pub async fn try_text(self) -> Result<String, Error> {
+        self.0.text().await.map_err(Error::Decode)
+    }
+
+    /// Get the status code of the response, like [`StatusCode::OK`] or
+    /// [`StatusCode::NOT_FOUND`].
+    pub fn status(&self) -> StatusCode {
+        self.0.status()
+    }
+
+    /// Get the headers of the response.
+    pub fn headers(&self) -> &HeaderMap {
+        self.0.headers()
+    }
+
+    /// Get the URL the response came from, which is not the one you asked for
+    /// if the server redirected the request.
+    pub fn url(&self) -> &Url {
+        self.0.url()
+    }
+
+    /// Get the full response body as bytes, for responses which are not text.
+    ///
+    /// If the body cannot be read, this panics instead of returning a
+    /// [`Result`] (for convenience in the demo).
+    pub async fn bytes(self) -> Vec<u8> {
+        self.try_bytes().await.unwrap()
+    }
+
+    /// Get the full response body as bytes, returning [`Error::Decode`] instead
+    /// of panicking if it cannot be read.
+    pub async fn try_bytes(self) -> Result<Vec<u8>, Error> {
+        let bytes = self.0.bytes().await.map_err(Error::Decode)?;
+        Ok(bytes.to_vec())
+    }
+
+    /// Deserialize the response body from JSON into any type which implements
+    /// [`serde::Deserialize`]. Only available with the `serde` feature.
+    ///
+    /// If the body is not valid JSON for the type, this panics instead of
+    /// returning a [`Result`] (for convenience in the demo).
+    #[cfg(feature = "serde")]
+    pub async fn json<T: serde::de::DeserializeOwned>(self) -> T {
+        self.try_json().await.unwrap()
+    }
+
+    /// Deserialize the response body from JSON, returning [`Error::Decode`]
+    /// instead of panicking if it cannot be read or is not valid JSON for the
+    /// type. Only available with the `serde` feature.
+    #[cfg(feature = "serde")]
+    pub async fn try_json<T: serde::de::DeserializeOwned>(
+        self,
+    ) -> Result<T, Error> {
+        self.0.json().await.map_err(Error::Decode)
+    }
+}
+
+/// A thin wrapper around [`reqwest::Client`], for when [`get`] is not enough:
+/// making requests other than `GET`, setting headers, or giving up on slow
+/// servers after a timeout.
+///
+/// A `Client` keeps connections open and reuses them for later requests to the
+/// same server, so make one and use it for all your requests rather than
+/// making a new one each time. Cloning a `Client` is cheap, and the clones
+/// share their connections.
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # trpl::block_on(async {
+/// let client = trpl::Client::builder()
+///     .timeout(Duration::from_secs(5))
+///     .user_agent("trpl-example")
+///     .build();
+/// let response = client
+///     .post("https://example.com/comments")
+///     .header("content-type", "text/plain")
+///     .body("Great book!")
+///     .send()
+///     .await;
+/// println!("{}", response.status());
+/// # });
+/// ```
+#[derive(Clone, Default)]
+pub struct Client(reqwest::Client);
+
+impl Client {
+    /// Make a client with the default settings: no timeout, and no headers
+    /// beyond the ones every request needs.
+    pub fn new() -> Client {
+        Client(reqwest::Client::new())
+    }
+
+    /// Start configuring a client; see [`ClientBuilder`].
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder(reqwest::Client::builder())
+    }
+
+    /// Start a `GET` request to a URL; see [`RequestBuilder`].
+    pub fn get(&self, url: &str) -> RequestBuilder {
+        RequestBuilder(self.0.get(url))
+    }
+
+    /// Start a `POST` request to a URL; see [`RequestBuilder`].
+    pub fn post(&self, url: &str) -> RequestBuilder {
+        RequestBuilder(self.0.post(url))
+    }
+}
+
+/// Settings for every request a [`Client`] makes.
+pub struct ClientBuilder(reqwest::ClientBuilder);
+
+impl ClientBuilder {
+    /// Give up on a request if it has not finished after `duration`,
+    /// including reading the whole response body.
+    pub fn timeout(self, duration: Duration) -> ClientBuilder {
+        ClientBuilder(self.0.timeout(duration))
+    }
+
+    /// Set the `User-Agent` header, which tells servers what is making the
+    /// request.
+    pub fn user_agent(self, user_agent: &str) -> ClientBuilder {
+        ClientBuilder(self.0.user_agent(user_agent))
+    }
+
+    /// Send a header with every request.
+    ///
+    /// If the name or value is not valid in a header, this panics instead of
+    /// returning a [`Result`] (for convenience in the demo).
+    pub fn header(self, name: &str, value: &str) -> ClientBuilder {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            reqwest::header::HeaderName::from_bytes(name.as_bytes()).unwrap(),
+            reqwest::header::HeaderValue::from_str(value).unwrap(),
+        );
+        ClientBuilder(self.0.default_headers(headers))
+    }
+
+    /// Make the client.
+    ///
+    /// If the client cannot be made, e.g. because TLS is not available, this
+    /// panics instead of returning a [`Result`] (for convenience in the demo).
+    pub fn build(self) -> Client {
+        Client(self.0.build().unwrap())
+    }
+}
+
+/// A request that has not been sent yet, made with [`Client::get`] or
+/// [`Client::post`].
+pub struct RequestBuilder(reqwest::RequestBuilder);
+
+impl RequestBuilder {
+    /// Add a header to this request.
+    ///
+    /// If the name or value is not valid in a header, sending the request
+    /// fails.
+    pub fn header(self, name: &str, value: &str) -> RequestBuilder {
+        RequestBuilder(self.0.header(name, value))
+    }
+
+    /// Give up on this request if it has not finished after `duration`,
+    /// instead of using the client's timeout.
+    pub fn timeout(self, duration: Duration) -> RequestBuilder {
+        RequestBuilder(self.0.timeout(duration))
+    }
+
+    /// Set the body of this request, e.g. for a `POST`.
+    pub fn body(self, body: impl Into<String>) -> RequestBuilder {
+        RequestBuilder(self.0.body(body.into()))
+    }
+
+    /// Send the request. For more convenient use in _The Rust Programming
+    /// Language_, panics instead of returning a [`Result`] if the request fails,
+    /// including if it times out.
+    pub async fn send(self) -> Response {
+        Response(self.0.send().await.unwrap())
+    }
+
+    /// Send the request, returning an [`Error`] instead of panicking if it
+    /// fails. Like [`try_get`], this treats a response with an error status
+    /// code as a failure.
+    pub async fn try_send(self) -> Result<Response, Error> {
+        error_for_status(self.0.send().await.map_err(Error::Network)?)
+    }
 }
 
 /// A thin wrapper around [`scraper::Html`] to make the demos in _The Rust
@@ -152,16 +708,134 @@ impl Html {
         }
     }
 
-    /// Get the first item in the document matching a string selector. Returns
-    /// Some()
+    /// Get the first item in the document matching a string selector, or
+    /// `None` if nothing matches.
     ///
     /// If the selector is not a valid CSS selector, panics rather than
     /// returning a [`Result`] for convenience.
     pub fn select_first<'a>(
         &'a self,
         selector: &'a str,
-    ) -> Option<scraper::ElementRef<'a>> {
-        let selector = scraper::Selector::parse(selector).unwrap();
-        self.inner.select(&selector).nth(0)
+    ) -> Option<Element<'a>> {
+        self.try_select_first(selector).unwrap()
+    }
+
+    /// Get the first item in the document matching a string selector, if any,
+    /// returning [`Error::Selector`] instead of panicking if the selector is
+    /// not a valid CSS selector.
+    pub fn try_select_first<'a>(
+        &'a self,
+        selector: &'a str,
+    ) -> Result<Option<Element<'a>>, Error> {
+        let selector = parse_selector(selector)?;
+        Ok(self.inner.select(&selector).nth(0).map(Element))
+    }
+
+    /// Get every item in the document matching a string selector, in the
+    /// order they appear in the document.
+    ///
+    /// If the selector is not a valid CSS selector, panics rather than
+    /// returning a [`Result`] for convenience.
+    pub fn select_all<'a>(
+        &'a self,
+        selector: &str,
+    ) -> impl Iterator<Item = Element<'a>> + use<'a> {
+        self.try_select_all(selector).unwrap()
+    }
+
+    /// Get every item in the document matching a string selector, returning
+    /// [`Error::Selector`] instead of panicking if the selector is not a valid
+    /// CSS selector.
+    pub fn try_select_all<'a>(
+        &'a self,
+        selector: &str,
+    ) -> Result<impl Iterator<Item = Element<'a>> + use<'a>, Error> {
+        let selector = parse_selector(selector)?;
+        let elements: Vec<_> =
+            self.inner.select(&selector).map(Element).collect();
+        Ok(elements.into_iter())
+    }
+}
+
+fn parse_selector(selector: &str) -> Result<scraper::Selector, Error> {
+    scraper::Selector::parse(selector)
+        .map_err(|error| Error::Selector(error.to_string()))
+}
+
+/// An element in an [`Html`] document, like a `<p>` or an `<a>`, with the
+/// methods you need to get at what is in it without depending on `scraper`
+/// yourself.
+///
+/// [`Html::select_first`] and [`Html::select_all`] give you these. If you are
+/// using `scraper` directly, you can turn one of its [`scraper::ElementRef`]s
+/// into an `Element` with `Element::from`.
+#[derive(Clone, Copy, Debug)]
+pub struct Element<'a>(scraper::ElementRef<'a>);
+
+impl<'a> Element<'a> {
+    /// Get all the text in the element and the elements inside it, without any
+    /// of the tags: `Hello, world!` for `<p>Hello, <b>world</b>!</p>`.
+    pub fn text(&self) -> String {
+        self.0.text().collect()
+    }
+
+    /// Get the HTML inside the element, without the element's own tags:
+    /// `Hello, <b>world</b>!` for `<p>Hello, <b>world</b>!</p>`.
+    pub fn inner_html(&self) -> String {
+        self.0.inner_html()
+    }
+
+    /// Get the value of one of the element's attributes, if it has it:
+    /// `Some("/about")` for `attr("href")` on `<a href="/about">`.
+    pub fn attr(&self, name: &str) -> Option<&'a str> {
+        self.0.value().attr(name)
+    }
+
+    /// Get the first element *inside* this one matching a string selector.
+    ///
+    /// If the selector is not a valid CSS selector, panics rather than
+    /// returning a [`Result`] for convenience.
+    pub fn select_first(&self, selector: &str) -> Option<Element<'a>> {
+        self.try_select_first(selector).unwrap()
+    }
+
+    /// Get the first element inside this one matching a string selector,
+    /// returning [`Error::Selector`] instead of panicking if the selector is
+    /// not a valid CSS selector.
+    pub fn try_select_first(
+        &self,
+        selector: &str,
+    ) -> Result<Option<Element<'a>>, Error> {
+        Ok(self.try_select_all(selector)?.next())
+    }
+
+    /// Get every element *inside* this one matching a string selector, in the
+    /// order they appear in the document.
+    ///
+    /// If the selector is not a valid CSS selector, panics rather than
+    /// returning a [`Result`] for convenience.
+    pub fn select_all(
+        &self,
+        selector: &str,
+    ) -> impl Iterator<Item = Element<'a>> + use<'a> {
+        self.try_select_all(selector).unwrap()
+    }
+
+    /// Get every element inside this one matching a string selector,
+    /// returning [`Error::Selector`] instead of panicking if the selector is
+    /// not a valid CSS selector.
+    pub fn try_select_all(
+        &self,
+        selector: &str,
+    ) -> Result<impl Iterator<Item = Element<'a>> + use<'a>, Error> {
+        let selector = parse_selector(selector)?;
+        let elements: Vec<_> = self.0.select(&selector).map(Element).collect();
+        Ok(elements.into_iter())
+    }
+}
+
+impl<'a> From<scraper::ElementRef<'a>> for Element<'a> {
+    fn from(element: scraper::ElementRef<'a>) -> Element<'a> {
+        Element(element)
     }
 }
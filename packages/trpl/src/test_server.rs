@@ -0,0 +1,366 @@
+//! A tiny HTTP server for trying out [`get`](crate::get) and
+//! [`Client`](crate::Client) without the internet. Only available with the
+//! `test-server` feature.
+//!
+//! The server runs on its own threads, listening on a port the operating
+//! system picks, so it works the same whether or not you are inside
+//! [`block_on`](crate::block_on), and many servers can run at once. It serves
+//! the pages you give it, each after however long a delay you like, which makes
+//! examples like racing two pages to see which loads first come out the same
+//! every time:
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use trpl::{Either, Html, test_server::TestServer};
+//!
+//! let server = TestServer::builder()
+//!     .slow_page("/slow", "<title>Slow</title>", Duration::from_millis(200))
+//!     .page("/fast", "<title>Fast</title>")
+//!     .start();
+//!
+//! let title = trpl::block_on(async {
+//!     let slow = async { trpl::get(&server.url("/slow")).await.text().await };
+//!     let fast = async { trpl::get(&server.url("/fast")).await.text().await };
+//!     let text = match trpl::select(slow, fast).await {
+//!         Either::Left(text) | Either::Right(text) => text,
+//!     };
+//!     Html::parse(&text)
+//!         .select_first("title")
+//!         .map(|title| title.inner_html())
+//! });
+//! assert_eq!(title, Some(String::from("Fast")));
+//! ```
+//!
+//! Any other path gets a `404 Not Found` response.
+//!
+//! Besides ordinary pages, the server can respond with other status codes,
+//! redirect, or cut a response off partway through, for trying out what
+//! happens when things go wrong. It also keeps track of the requests it
+//! receives, so you can check what a client sent; see [`TestServer::requests`].
+
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::StatusCode;
+
+/// A page the server responds with.
+#[derive(Clone)]
+struct Page {
+    status: StatusCode,
+    /// Any headers besides `Content-Type` and `Content-Length`.
+    headers: Vec<(String, String)>,
+    body: String,
+    latency: Duration,
+    /// Whether to close the connection halfway through sending the body.
+    truncated: bool,
+}
+
+impl Page {
+    fn new(body: &str, latency: Duration) -> Page {
+        Page {
+            status: StatusCode::OK,
+            headers: Vec::new(),
+            body: body.to_string(),
+            latency,
+            truncated: false,
+        }
+    }
+}
+
+/// The pages a [`TestServer`] will serve, before it starts.
+#[derive(Default)]
+pub struct Builder {
+    pages: HashMap<String, Page>,
+    latency: Duration,
+}
+
+impl Builder {
+    /// Serve `body` as HTML at `path`, e.g. `"/"` or `"/about"`, after the
+    /// server's default latency. Requests for `path` with a query string, like
+    /// `"/about?lang=en"`, get the same page.
+    pub fn page(self, path: &str, body: &str) -> Builder {
+        let latency = self.latency;
+        self.slow_page(path, body, latency)
+    }
+
+    /// Serve `body` as HTML at `path`, waiting for `latency` before responding.
+    pub fn slow_page(
+        mut self,
+        path: &str,
+        body: &str,
+        latency: Duration,
+    ) -> Builder {
+        self.pages
+            .insert(path.to_string(), Page::new(body, latency));
+        self
+    }
+
+    /// Serve `body` as HTML at `path` with the given `status` instead of
+    /// `200 OK`, like `StatusCode::INTERNAL_SERVER_ERROR`, after the server's
+    /// default latency.
+    pub fn status_page(
+        mut self,
+        path: &str,
+        status: StatusCode,
+        body: &str,
+    ) -> Builder {
+        let page = Page {
+            status,
+            ..Page::new(body, self.latency)
+        };
+        self.pages.insert(path.to_string(), page);
+        self
+    }
+
+    /// Redirect requests for `path` to `to`, with a `302 Found` response,
+    /// after the server's default latency.
+    pub fn redirect(mut self, path: &str, to: &str) -> Builder {
+        let page = Page {
+            status: StatusCode::FOUND,
+            headers: vec![(String::from("Location"), to.to_string())],
+            ..Page::new("", self.latency)
+        };
+        self.pages.insert(path.to_string(), page);
+        self
+    }
+
+    /// Start serving `body` as HTML at `path`, after the server's default
+    /// latency, but close the connection halfway through, so the client gets
+    /// an error reading the body.
+    pub fn truncated_page(mut self, path: &str, body: &str) -> Builder {
+        let page = Page {
+            truncated: true,
+            ..Page::new(body, self.latency)
+        };
+        self.pages.insert(path.to_string(), page);
+        self
+    }
+
+    /// Wait for `latency` before responding with any page added after this
+    /// with any method except [`Builder::slow_page`], and before responding
+    /// that a page is not found.
+    pub fn latency(mut self, latency: Duration) -> Builder {
+        self.latency = latency;
+        self
+    }
+
+    /// Start serving the pages.
+    ///
+    /// If there is no port free to listen on, this panics instead of returning
+    /// a [`Result`] (for convenience in the demo).
+    pub fn start(self) -> TestServer {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let stopped = Arc::new(AtomicBool::new(false));
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let site = Arc::new(Site {
+            pages: self.pages,
+            not_found: self.latency,
+            requests: Arc::clone(&requests),
+            connections: AtomicUsize::new(0),
+        });
+
+        let stopping = Arc::clone(&stopped);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stopping.load(Ordering::SeqCst) {
+                    break;
+                }
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                let site = Arc::clone(&site);
+                thread::spawn(move || site.serve(stream));
+            }
+        });
+
+        TestServer {
+            addr,
+            stopped,
+            requests,
+        }
+    }
+}
+
+/// A request a [`TestServer`] received.
+#[derive(Clone, Debug)]
+pub struct Request {
+    /// The method, like `GET` or `POST`.
+    pub method: String,
+    /// The path requested, like `/about`, with any query string, like
+    /// `/search?q=rust`.
+    pub path: String,
+    /// The headers, in the order the client sent them, with their names in
+    /// lowercase.
+    pub headers: Vec<(String, String)>,
+    /// The body, which is empty for most `GET` requests.
+    pub body: String,
+    /// Which connection the request came on, counting from 0 in the order the
+    /// server accepted them. Clients which reuse connections send many
+    /// requests on the same one.
+    pub connection: usize,
+}
+
+impl Request {
+    /// The value of the header called `name`, which is not case-sensitive, if
+    /// the client sent it.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// A running HTTP server which serves the pages it was built with, until it
+/// is dropped.
+pub struct TestServer {
+    addr: SocketAddr,
+    stopped: Arc<AtomicBool>,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl TestServer {
+    /// Start describing the pages to serve; see [`Builder`].
+    pub fn builder() -> Builder {
+        Builder::default()
+    }
+
+    /// The full URL of `path` on this server, like
+    /// `http://127.0.0.1:50123/about` for `"/about"`.
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{path}", self.addr)
+    }
+
+    /// The address the server is listening on.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Every request the server has received so far, in the order it received
+    /// them. A request is recorded before the server responds to it, so once
+    /// a client has its response, the request is here.
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // Wake up the thread waiting for connections so it sees it should
+        // stop.
+        let _ = TcpStream::connect(self.addr);
+    }
+}
+
+/// Everything the threads answering requests share.
+struct Site {
+    pages: HashMap<String, Page>,
+    /// The latency for pages which are not found.
+    not_found: Duration,
+    requests: Arc<Mutex<Vec<Request>>>,
+    connections: AtomicUsize,
+}
+
+impl Site {
+    /// Answer the requests on `stream` until the client closes it.
+    fn serve(&self, stream: TcpStream) {
+        let connection = self.connections.fetch_add(1, Ordering::SeqCst);
+        let mut reader = BufReader::new(&stream);
+        while let Some(request) = read_request(&mut reader, connection) {
+            // Pages are served whatever the query string, like a static site.
+            let path = match request.path.split_once('?') {
+                Some((path, _query)) => path,
+                None => &request.path,
+            };
+            let page = match self.pages.get(path) {
+                Some(page) => page.clone(),
+                None => Page {
+                    status: StatusCode::NOT_FOUND,
+                    ..Page::new("Not Found", self.not_found)
+                },
+            };
+            self.requests.lock().unwrap().push(request);
+
+            thread::sleep(page.latency);
+            let mut response = format!(
+                "HTTP/1.1 {}\r\n\
+                 Content-Type: text/html; charset=utf-8\r\n\
+                 Content-Length: {}\r\n",
+                page.status,
+                page.body.len()
+            );
+            for (name, value) in &page.headers {
+                response.push_str(&format!("{name}: {value}\r\n"));
+            }
+            response.push_str("\r\n");
+            let mut response = response.into_bytes();
+            // Cut by bytes rather than characters: the body only has to stop
+            // short of its `Content-Length`, even mid-character.
+            let body = page.body.as_bytes();
+            if page.truncated {
+                response.extend_from_slice(&body[..body.len() / 2]);
+            } else {
+                response.extend_from_slice(body);
+            }
+            if (&stream).write_all(&response).is_err() || page.truncated {
+                return;
+            }
+        }
+    }
+}
+
+/// Read the next request on a connection, or `None` if the client closed it.
+fn read_request(
+    reader: &mut BufReader<&TcpStream>,
+    connection: usize,
+) -> Option<Request> {
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).ok()? == 0 {
+        return None;
+    }
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("GET").to_string();
+    let path = parts.next().unwrap_or("/").to_string();
+
+    // Read the rest of the request, so the client isn't cut off mid-send.
+    let mut headers = Vec::new();
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        match reader.read_line(&mut line) {
+            Ok(0) | Err(_) => return None,
+            Ok(_) if line.trim().is_empty() => break,
+            Ok(_) => {
+                if let Some((name, value)) = line.split_once(':') {
+                    let (name, value) = (name.to_lowercase(), value.trim());
+                    if name == "content-length" {
+                        content_length = value.parse().unwrap_or(0);
+                    }
+                    headers.push((name, value.to_string()));
+                }
+            }
+        }
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).ok()?;
+
+    Some(Request {
+        method,
+        path,
+        headers,
+        body: String::from_utf8_lossy(&body).into_owned(),
+        connection,
+    })
+}
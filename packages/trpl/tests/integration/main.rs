@@ -12,7 +12,10 @@
 use std::{pin::Pin, time::Duration};
 
 use futures::Future;
-use trpl::{Either, Receiver, Sender};
+use trpl::{
+    BroadcastReceiver, BroadcastSender, Either, OneshotReceiver, OneshotSender,
+    Receiver, Sender, SyncReceiver, SyncSender,
+};
 
 /// We initially named the function `run` and an online version of the async chapter
 /// was released with that name, so we want to keep it working. We decided to rename
@@ -35,6 +38,87 @@ fn re_exported_block_on_works() {
     assert_eq!(val, "Hello");
 }
 
+/// Tasks would be canceled when `block_on` returns if each call made its own
+/// runtime.
+#[test]
+fn block_on_reuses_one_runtime() {
+    let (tx, rx) = std::sync::mpsc::channel();
+    trpl::block_on(async {
+        trpl::spawn_task(async move {
+            trpl::sleep(Duration::from_millis(1)).await;
+            tx.send("Still running").unwrap();
+        });
+    });
+    assert_eq!(rx.recv_timeout(Duration::from_secs(5)), Ok("Still running"));
+}
+
+#[test]
+#[should_panic(expected = "use `.await` instead")]
+fn nested_block_on_panics_clearly() {
+    trpl::block_on(async {
+        trpl::block_on(async {});
+    });
+}
+
+#[test]
+fn block_on_works_where_blocking_is_allowed() {
+    let text = trpl::block_on(async {
+        tokio::task::spawn_blocking(|| trpl::block_on(async { "Blocking" }))
+            .await
+            .unwrap()
+    });
+    assert_eq!(text, "Blocking");
+
+    // Entering a runtime only makes it the current one; nothing is running
+    // on this thread.
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let _guard = runtime.enter();
+    assert_eq!(trpl::block_on(async { "Entered" }), "Entered");
+}
+
+#[test]
+#[should_panic(expected = "Inner")]
+fn block_on_keeps_panics_from_the_future() {
+    trpl::block_on(async { panic!("Inner") });
+}
+
+#[test]
+fn block_on_current_thread_interleaves_the_same_way_every_time() {
+    fn interleave() -> (Vec<String>, std::thread::ThreadId) {
+        trpl::block_on_current_thread(async {
+            let (tx, mut rx) = trpl::channel();
+            let tx2 = tx.clone();
+            let a = trpl::spawn_task(async move {
+                for i in 0..3 {
+                    tx.send(format!("a{i}")).unwrap();
+                    trpl::yield_now().await;
+                }
+            });
+            let b = trpl::spawn_task(async move {
+                for i in 0..3 {
+                    tx2.send(format!("b{i}")).unwrap();
+                    trpl::yield_now().await;
+                }
+            });
+            a.await.unwrap();
+            b.await.unwrap();
+
+            let mut order = Vec::new();
+            while let Some(message) = rx.recv().await {
+                order.push(message);
+            }
+            (order, std::thread::current().id())
+        })
+    }
+
+    let (first, thread) = interleave();
+    assert_eq!(first.len(), 6);
+    assert_eq!(thread, std::thread::current().id());
+    for _ in 0..10 {
+        assert_eq!(interleave().0, first);
+    }
+}
+
 #[test]
 fn re_exported_spawn_works() {
     let result = trpl::block_on(async {
@@ -72,6 +156,216 @@ fn re_exported_channel_apis_work() {
     });
 }
 
+#[test]
+fn re_exported_sync_channel_apis_work() {
+    trpl::block_on(async {
+        // Explicitly naming the type to confirm the re-exports are aligned.
+        let (tx, mut rx): (SyncSender<&str>, SyncReceiver<&str>) =
+            trpl::sync_channel(1);
+
+        tx.send("Hello").await.unwrap();
+        // The channel is full, so there is no room for another message until
+        // the first one is received.
+        assert!(tx.try_send("Goodbye").is_err());
+        assert_eq!(rx.recv().await, Some("Hello"));
+
+        tx.send("Goodbye").await.unwrap();
+        drop(tx);
+
+        assert_eq!(rx.recv().await, Some("Goodbye"));
+        assert_eq!(rx.recv().await, None);
+    });
+}
+
+#[test]
+fn sync_channel_applies_backpressure() {
+    let order = trpl::block_on(async {
+        let (tx, mut rx) = trpl::sync_channel(1);
+        let (log_tx, mut log_rx) = trpl::channel();
+
+        let log = log_tx.clone();
+        let send = async move {
+            for n in 1..=3 {
+                tx.send(n).await.unwrap();
+                log.send(format!("sent {n}")).unwrap();
+            }
+        };
+        let receive = async move {
+            trpl::sleep(Duration::from_millis(10)).await;
+            while let Some(n) = rx.recv().await {
+                log_tx.send(format!("received {n}")).unwrap();
+            }
+        };
+        trpl::join(send, receive).await;
+
+        let mut order = Vec::new();
+        while let Some(entry) = log_rx.recv().await {
+            order.push(entry);
+        }
+        order
+    });
+
+    // Only one message fits in the channel, so the sender has to wait for the
+    // receiver before it can send the second one.
+    assert_eq!(order[0], "sent 1");
+    assert_eq!(order[1], "received 1");
+    assert_eq!(order.len(), 6);
+}
+
+#[test]
+fn re_exported_oneshot_apis_work() {
+    trpl::block_on(async {
+        let (tx, rx): (OneshotSender<&str>, OneshotReceiver<&str>) =
+            trpl::oneshot();
+
+        trpl::spawn_task(async move {
+            trpl::sleep(Duration::from_millis(1)).await;
+            tx.send("Hello").unwrap();
+        });
+
+        assert_eq!(rx.await, Ok("Hello"));
+
+        let (tx, rx) = trpl::oneshot::<&str>();
+        drop(tx);
+        assert!(rx.await.is_err());
+    });
+}
+
+#[test]
+fn re_exported_broadcast_apis_work() {
+    trpl::block_on(async {
+        let (tx, mut rx1): (BroadcastSender<&str>, BroadcastReceiver<&str>) =
+            trpl::broadcast(16);
+        let mut rx2 = tx.subscribe();
+
+        tx.send("Hello").unwrap();
+        tx.send("Goodbye").unwrap();
+        drop(tx);
+
+        for rx in [&mut rx1, &mut rx2] {
+            assert_eq!(rx.recv().await, Ok("Hello"));
+            assert_eq!(rx.recv().await, Ok("Goodbye"));
+            assert!(rx.recv().await.is_err());
+        }
+    });
+}
+
+mod channel_streams_work {
+    use trpl::{
+        BroadcastStream, BroadcastStreamRecvError, OneshotStream,
+        ReceiverStream, StreamExt, SyncReceiverStream,
+    };
+
+    use super::*;
+
+    #[test]
+    fn receiver_stream() {
+        let messages = trpl::block_on(async {
+            let (tx, rx) = trpl::channel();
+            tx.send(1).unwrap();
+            tx.send(2).unwrap();
+            drop(tx);
+            ReceiverStream::new(rx).collect::<Vec<_>>().await
+        });
+
+        assert_eq!(messages, vec![1, 2]);
+    }
+
+    #[test]
+    fn sync_receiver_stream() {
+        let messages = trpl::block_on(async {
+            let (tx, rx) = trpl::sync_channel(1);
+            let send = async move {
+                for n in 1..=3 {
+                    tx.send(n).await.unwrap();
+                }
+            };
+            let receive = SyncReceiverStream::new(rx).collect::<Vec<_>>();
+            trpl::join(send, receive).await.1
+        });
+
+        assert_eq!(messages, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn oneshot_stream() {
+        let (sent, dropped) = trpl::block_on(async {
+            let (tx, rx) = trpl::oneshot();
+            tx.send("Hello").unwrap();
+            let sent = OneshotStream::new(rx).collect::<Vec<_>>().await;
+
+            let (tx, rx) = trpl::oneshot::<&str>();
+            drop(tx);
+            let dropped = OneshotStream::from(rx).collect::<Vec<_>>().await;
+
+            (sent, dropped)
+        });
+
+        assert_eq!(sent, vec!["Hello"]);
+        assert!(dropped.is_empty());
+    }
+
+    #[test]
+    fn oneshot_stream_stops_another_stream() {
+        let ticks = trpl::block_on(async {
+            let (stop_tx, stop_rx) = trpl::oneshot();
+            let (tx, rx) = trpl::channel();
+            trpl::spawn_task(async move {
+                for n in 1..=3 {
+                    tx.send(Some(n)).unwrap();
+                    trpl::sleep(Duration::from_millis(10)).await;
+                }
+                stop_tx.send(None).unwrap();
+                // Keep the channel open, so only the stop signal can end it.
+                trpl::sleep(Duration::from_secs(10)).await;
+                drop(tx);
+            });
+
+            let stop = OneshotStream::new(stop_rx);
+            let mut merged = ReceiverStream::new(rx).merge(stop);
+            let mut ticks = Vec::new();
+            while let Some(Some(n)) = merged.next().await {
+                ticks.push(n);
+            }
+            ticks
+        });
+
+        assert_eq!(ticks, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn broadcast_stream() {
+        let messages = trpl::block_on(async {
+            let (tx, rx) = trpl::broadcast(16);
+            let stream = BroadcastStream::new(rx);
+            tx.send(1).unwrap();
+            tx.send(2).unwrap();
+            drop(tx);
+            stream.collect::<Vec<_>>().await
+        });
+
+        assert_eq!(messages, vec![Ok(1), Ok(2)]);
+    }
+
+    #[test]
+    fn broadcast_stream_reports_lagging() {
+        let messages = trpl::block_on(async {
+            let (tx, rx) = trpl::broadcast(2);
+            let stream = BroadcastStream::new(rx);
+            for n in 1..=3 {
+                tx.send(n).unwrap();
+            }
+            drop(tx);
+            stream.collect::<Vec<_>>().await
+        });
+
+        assert_eq!(
+            messages,
+            vec![Err(BroadcastStreamRecvError::Lagged(1)), Ok(2), Ok(3)]
+        );
+    }
+}
+
 mod re_exported_join_apis_work {
     use super::*;
 
@@ -191,6 +485,165 @@ fn race_continues_to_work() {
     assert!(matches!(val, Either::Right(Fast)));
 }
 
+mod select_apis_work {
+    use std::{
+        rc::Rc,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use super::*;
+
+    #[test]
+    fn select_all_returns_the_index_and_drops_the_rest() {
+        struct CountDrops<'a>(&'a AtomicUsize);
+
+        impl Drop for CountDrops<'_> {
+            fn drop(&mut self) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let dropped = AtomicUsize::new(0);
+        let result = trpl::block_on(async {
+            let futures = [1_000, 1, 500].map(|ms| {
+                let guard = CountDrops(&dropped);
+                async move {
+                    let _guard = guard;
+                    trpl::sleep(Duration::from_millis(ms)).await;
+                    ms
+                }
+            });
+            let result = trpl::select_all(futures).await;
+            (result, dropped.load(Ordering::SeqCst))
+        });
+
+        // The winner and both losers have all been dropped by the time
+        // `select_all` returns.
+        assert_eq!(result, ((1, 1), 3));
+    }
+
+    #[test]
+    fn select_all_prefers_the_first_ready_future() {
+        let result = trpl::block_on(trpl::select_all([
+            trpl::boxed(async {
+                trpl::sleep(Duration::from_millis(1_000)).await;
+                "slow"
+            }),
+            trpl::boxed(async { "first" }),
+            trpl::boxed(async { "second" }),
+        ]));
+
+        assert_eq!(result, (1, "first"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn select_all_panics_when_empty() {
+        trpl::block_on(trpl::select_all(Vec::<trpl::BoxFuture<()>>::new()));
+    }
+
+    #[test]
+    fn select_macro_with_different_outputs() {
+        let results: Vec<String> = [0, 1, 2]
+            .into_iter()
+            .map(|winner| {
+                let delay = move |branch| {
+                    Duration::from_millis(if branch == winner {
+                        1
+                    } else {
+                        1_000
+                    })
+                };
+                trpl::block_on(async move {
+                    trpl::select! {
+                        n = async {
+                            trpl::sleep(delay(0)).await;
+                            42
+                        } => format!("number {n}"),
+                        text = async {
+                            trpl::sleep(delay(1)).await;
+                            "hello"
+                        } => format!("text {text}"),
+                        () = trpl::sleep(delay(2)) => String::from("sleep"),
+                    }
+                })
+            })
+            .collect();
+
+        assert_eq!(results, vec!["number 42", "text hello", "sleep"]);
+    }
+
+    #[test]
+    fn select_macro_prefers_earlier_branches() {
+        let result = trpl::block_on(async {
+            trpl::select! {
+                _ = trpl::sleep(Duration::from_millis(1_000)) => 0,
+                a = async { 1 } => a,
+                b = async { 2 } => b,
+                c = async { 3 } => c,
+            }
+        });
+
+        assert_eq!(result, 1);
+    }
+
+    #[test]
+    fn select_macro_bodies_can_use_control_flow() {
+        let result = trpl::block_on(async {
+            let mut ticks = Vec::new();
+            for tick in 0.. {
+                trpl::select! {
+                    () = trpl::sleep(Duration::from_millis(1_000)) => break,
+                    t = async { tick } => ticks.push(t),
+                    () = async {} => unreachable!(),
+                }
+                if ticks.len() == 3 {
+                    break;
+                }
+            }
+            ticks
+        });
+
+        assert_eq!(result, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn boxed_futures_join_all() {
+        let result = trpl::block_on(async {
+            let shared = Rc::new(String::from("shared"));
+            let borrowed = String::from("borrowed");
+            let futures = vec![
+                trpl::boxed(async { String::from("owned") }),
+                trpl::boxed(async {
+                    trpl::sleep(Duration::from_millis(1)).await;
+                    borrowed.clone()
+                }),
+                trpl::boxed(async move { shared.to_string() }),
+            ];
+            trpl::join_all(futures).await
+        });
+
+        assert_eq!(result, vec!["owned", "borrowed", "shared"]);
+    }
+
+    #[test]
+    fn boxed_futures_join_macro() {
+        let result = trpl::block_on(async {
+            let futures = [
+                trpl::boxed(async { 1 }),
+                trpl::boxed(async {
+                    trpl::yield_now().await;
+                    2
+                }),
+            ];
+            let [a, b] = futures;
+            trpl::join!(a, b, trpl::boxed(async { 3 }))
+        });
+
+        assert_eq!(result, (1, 2, 3));
+    }
+}
+
 #[test]
 fn yield_now() {
     let result = trpl::block_on(async {
@@ -275,3 +728,513 @@ fn re_exported_html() {
     let p = doc.select_first("p").map(|el| el.inner_html());
     assert_eq!(p, Some(String::from("Hello!")));
 }
+
+#[test]
+fn re_exported_html_try_select_first() {
+    use trpl::Html;
+
+    let doc = Html::parse("<p>Hello!</p>");
+    let p = doc.try_select_first("p").unwrap().map(|el| el.inner_html());
+    assert_eq!(p, Some(String::from("Hello!")));
+    assert!(matches!(
+        doc.try_select_first("p[").map(|el| el.is_some()),
+        Err(trpl::Error::Selector(_))
+    ));
+}
+
+mod html_apis_work {
+    use trpl::Html;
+
+    const PAGE: &str = r#"<html><body>
+        <ul>
+            <li><a href="/one">One</a></li>
+            <li><a href="/two" title="Second">Two <b>and a bit</b></a></li>
+        </ul>
+        <p>Not a <a href="/three">list item</a></p>
+    </body></html>"#;
+
+    #[test]
+    fn select_all() {
+        let doc = Html::parse(PAGE);
+        let hrefs: Vec<_> =
+            doc.select_all("a").filter_map(|a| a.attr("href")).collect();
+        assert_eq!(hrefs, vec!["/one", "/two", "/three"]);
+        assert_eq!(doc.select_all("table").count(), 0);
+        assert!(matches!(
+            doc.try_select_all("a[").map(|elements| elements.count()),
+            Err(trpl::Error::Selector(_))
+        ));
+    }
+
+    #[test]
+    fn element_text_html_and_attributes() {
+        let doc = Html::parse(PAGE);
+        let second = doc.select_all("li a").nth(1).unwrap();
+        assert_eq!(second.text(), "Two and a bit");
+        assert_eq!(second.inner_html(), "Two <b>and a bit</b>");
+        assert_eq!(second.attr("title"), Some("Second"));
+        assert_eq!(second.attr("rel"), None);
+
+        let first = doc.select_first("a").unwrap();
+        assert_eq!(first.text(), "One");
+    }
+
+    #[test]
+    fn nested_select() {
+        let doc = Html::parse(PAGE);
+        let list = doc.select_all("ul").next().unwrap();
+        let texts: Vec<_> = list.select_all("a").map(|a| a.text()).collect();
+        assert_eq!(texts, vec!["One", "Two and a bit"]);
+        assert_eq!(
+            list.select_first("b").map(|b| b.inner_html()),
+            Some(String::from("and a bit"))
+        );
+        assert!(list.select_first("p").is_none());
+        assert!(list.try_select_first("[").is_err());
+    }
+}
+
+#[cfg(feature = "test-server")]
+mod fallible_http_apis_work {
+    use std::net::TcpListener;
+
+    use trpl::test_server::TestServer;
+
+    #[test]
+    fn try_get_and_try_text() {
+        let server = TestServer::builder().page("/", "Hello!").start();
+        let text = trpl::block_on(async {
+            let response = trpl::try_get(&server.url("/")).await.unwrap();
+            response.try_text().await.unwrap()
+        });
+        assert_eq!(text, "Hello!");
+    }
+
+    #[test]
+    fn try_get_error_status() {
+        let server = TestServer::builder().start();
+        let result = trpl::block_on(trpl::try_get(&server.url("/missing")));
+        match result {
+            Err(trpl::Error::Status(status)) => assert_eq!(status, 404),
+            _ => panic!("expected a status error"),
+        }
+    }
+
+    #[test]
+    fn try_get_network_error() {
+        // Nothing is listening on the port once the listener is dropped.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        drop(listener);
+
+        let result = trpl::block_on(trpl::try_get(&url));
+        assert!(matches!(result, Err(trpl::Error::Network(_))));
+    }
+
+    #[test]
+    fn try_text_decode_error() {
+        // The connection closes before the promised body arrives.
+        let server = TestServer::builder()
+            .truncated_page("/", "Hello, world!")
+            .start();
+        let result = trpl::block_on(async {
+            trpl::try_get(&server.url("/"))
+                .await
+                .unwrap()
+                .try_text()
+                .await
+        });
+        let error = result.unwrap_err();
+        assert!(matches!(error, trpl::Error::Decode(_)));
+        assert!(std::error::Error::source(&error).is_some());
+    }
+
+    #[test]
+    fn try_text_decode_error_mid_character() {
+        // Half of the body ends partway through the `é`.
+        let server = TestServer::builder().truncated_page("/", "Café").start();
+        let result = trpl::block_on(async {
+            trpl::try_get(&server.url("/"))
+                .await
+                .unwrap()
+                .try_text()
+                .await
+        });
+        assert!(matches!(result, Err(trpl::Error::Decode(_))));
+    }
+}
+
+#[cfg(feature = "test-server")]
+mod response_apis_work {
+    use trpl::test_server::TestServer;
+
+    #[test]
+    fn status_headers_url_and_bytes() {
+        let server = TestServer::builder()
+            .redirect("/", "/moved")
+            .page("/moved", "abc")
+            .start();
+        let (status, content_type, final_url, bytes) = trpl::block_on(async {
+            let response = trpl::get(&server.url("/")).await;
+            (
+                response.status(),
+                response.headers()["content-type"]
+                    .to_str()
+                    .unwrap()
+                    .to_owned(),
+                response.url().to_string(),
+                response.bytes().await,
+            )
+        });
+        assert_eq!(status, trpl::StatusCode::OK);
+        assert_eq!(content_type, "text/html; charset=utf-8");
+        assert_eq!(final_url, server.url("/moved"));
+        assert_eq!(bytes, b"abc".to_vec());
+    }
+
+    #[test]
+    fn error_status_is_not_a_failure_for_get() {
+        let server = TestServer::builder()
+            .status_page("/", trpl::StatusCode::NOT_FOUND, "Gone")
+            .start();
+        let (status, text) = trpl::block_on(async {
+            let response = trpl::get(&server.url("/")).await;
+            (response.status(), response.text().await)
+        });
+        assert_eq!(status, trpl::StatusCode::NOT_FOUND);
+        assert_eq!(text, "Gone");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json() {
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct Page {
+            title: String,
+            views: u32,
+        }
+
+        let server = TestServer::builder()
+            .page("/page", r#"{"title": "Async", "views": 17}"#)
+            .page("/list", "[]")
+            .start();
+        let (page, wrong_type) = trpl::block_on(async {
+            let page: Page = trpl::get(&server.url("/page")).await.json().await;
+            let wrong_type = trpl::get(&server.url("/list"))
+                .await
+                .try_json::<Page>()
+                .await;
+            (page, wrong_type)
+        });
+        assert_eq!(
+            page,
+            Page {
+                title: String::from("Async"),
+                views: 17
+            }
+        );
+        assert!(matches!(wrong_type, Err(trpl::Error::Decode(_))));
+    }
+}
+
+#[cfg(feature = "test-server")]
+mod client_apis_work {
+    use trpl::test_server::TestServer;
+
+    use super::*;
+
+    #[test]
+    fn headers_user_agent_and_post_body() {
+        let server = TestServer::builder().page("/", "OK").start();
+        let text = trpl::block_on(async {
+            let client = trpl::Client::builder()
+                .user_agent("trpl-test")
+                .header("x-book", "trpl")
+                .build();
+            client
+                .post(&server.url("/"))
+                .header("x-chapter", "17")
+                .body("Hello!")
+                .send()
+                .await
+                .text()
+                .await
+        });
+        assert_eq!(text, "OK");
+
+        let requests = server.requests();
+        let [request] = requests.as_slice() else {
+            panic!("expected one request, got {requests:?}");
+        };
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/");
+        assert_eq!(request.header("User-Agent"), Some("trpl-test"));
+        assert_eq!(request.header("x-book"), Some("trpl"));
+        assert_eq!(request.header("x-chapter"), Some("17"));
+        assert_eq!(request.body, "Hello!");
+    }
+
+    #[test]
+    fn reuses_connections() {
+        let server = TestServer::builder().page("/", "OK").start();
+        trpl::block_on(async {
+            let client = trpl::Client::new();
+            client.get(&server.url("/")).send().await.text().await;
+            client
+                .clone()
+                .get(&server.url("/"))
+                .send()
+                .await
+                .text()
+                .await;
+        });
+        let connections: Vec<_> = server
+            .requests()
+            .iter()
+            .map(|request| request.connection)
+            .collect();
+        assert_eq!(connections, vec![0, 0]);
+    }
+
+    #[test]
+    fn timeouts() {
+        // The server takes far longer to answer than the client will wait.
+        let server = TestServer::builder()
+            .latency(Duration::from_secs(5))
+            .page("/", "Too late")
+            .start();
+        let url = server.url("/");
+
+        let (from_client, from_request) = trpl::block_on(async {
+            let client = trpl::Client::builder()
+                .timeout(Duration::from_millis(50))
+                .build();
+            let from_client = client.get(&url).try_send().await;
+            let from_request = trpl::Client::new()
+                .get(&url)
+                .timeout(Duration::from_millis(50))
+                .try_send()
+                .await;
+            (from_client, from_request)
+        });
+        for result in [from_client, from_request] {
+            let error = result.err().unwrap();
+            assert!(matches!(error, trpl::Error::Network(_)));
+            assert!(error.is_timeout());
+        }
+    }
+
+    #[test]
+    fn try_send_error_status() {
+        let server = TestServer::builder()
+            .status_page("/", trpl::StatusCode::INTERNAL_SERVER_ERROR, "Oops")
+            .start();
+        let result = trpl::block_on(
+            trpl::Client::new().get(&server.url("/")).try_send(),
+        );
+        assert!(matches!(
+            result,
+            Err(trpl::Error::Status(trpl::StatusCode::INTERNAL_SERVER_ERROR))
+        ));
+    }
+}
+
+#[cfg(feature = "test-server")]
+mod test_server_works {
+    use std::time::Instant;
+
+    use trpl::test_server::TestServer;
+
+    use super::*;
+
+    #[test]
+    fn get_and_text() {
+        let server = TestServer::builder()
+            .page("/", "<p>Home</p>")
+            .page("/about", "<p>About</p>")
+            .start();
+        let (home, about) = trpl::block_on(async {
+            let home = trpl::get(&server.url("/")).await.text().await;
+            let about = trpl::get(&server.url("/about")).await.text().await;
+            (home, about)
+        });
+        assert_eq!(home, "<p>Home</p>");
+        assert_eq!(about, "<p>About</p>");
+    }
+
+    #[test]
+    fn query_strings_get_the_same_page() {
+        let server = TestServer::builder().page("/page", "Page").start();
+        let text = trpl::block_on(async {
+            trpl::get(&server.url("/page?x=1")).await.text().await
+        });
+        assert_eq!(text, "Page");
+        assert_eq!(server.requests()[0].path, "/page?x=1");
+    }
+
+    #[test]
+    fn missing_pages_are_not_found() {
+        let server = TestServer::builder().start();
+        let response = trpl::block_on(trpl::get(&server.url("/missing")));
+        assert_eq!(response.status(), trpl::StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn latency_decides_races() {
+        let server = TestServer::builder()
+            .latency(Duration::from_millis(100))
+            .page("/default", "default")
+            .slow_page("/slow", "slow", Duration::from_millis(300))
+            .slow_page("/fast", "fast", Duration::ZERO)
+            .start();
+
+        let start = Instant::now();
+        let text = trpl::block_on(trpl::get(&server.url("/default"))).status();
+        assert_eq!(text, trpl::StatusCode::OK);
+        assert!(start.elapsed() >= Duration::from_millis(100));
+
+        let winner = trpl::block_on(async {
+            let slow =
+                async { trpl::get(&server.url("/slow")).await.text().await };
+            let fast =
+                async { trpl::get(&server.url("/fast")).await.text().await };
+            trpl::select(slow, fast).await
+        });
+        assert!(matches!(winner, Either::Right(text) if text == "fast"));
+    }
+
+    #[test]
+    fn stops_when_dropped() {
+        let server = TestServer::builder().page("/", "").start();
+        let url = server.url("/");
+        drop(server);
+        // Give the server's thread a moment to stop listening.
+        std::thread::sleep(Duration::from_millis(50));
+        let result = trpl::block_on(trpl::try_get(&url));
+        assert!(matches!(result, Err(trpl::Error::Network(_))));
+    }
+}
+
+mod attribute_macros_work {
+    use super::*;
+
+    #[trpl::main]
+    async fn main_like() -> &'static str {
+        trpl::sleep(Duration::from_millis(1)).await;
+        "Hello"
+    }
+
+    #[test]
+    fn main() {
+        assert_eq!(main_like(), "Hello");
+    }
+
+    #[trpl::test]
+    async fn test() {
+        let (tx, mut rx) = trpl::channel();
+        trpl::spawn_task(async move { tx.send("Hello").unwrap() });
+        assert_eq!(rx.recv().await, Some("Hello"));
+    }
+
+    #[trpl::test]
+    async fn test_returning_result() -> Result<(), String> {
+        let text = trpl::read_to_string("tests/integration/to-read.txt")
+            .await
+            .map_err(|e| e.to_string())?;
+        assert_eq!(text, "This is some text!\n");
+        Ok(())
+    }
+
+    #[trpl::test]
+    #[should_panic(expected = "Goodbye")]
+    async fn test_keeps_other_attributes() {
+        trpl::yield_now().await;
+        panic!("Goodbye");
+    }
+}
+
+mod timeout_works {
+    use super::*;
+
+    #[test]
+    fn finishes_in_time() {
+        let result =
+            trpl::block_on(trpl::timeout(Duration::from_secs(5), async {
+                "Done!"
+            }));
+        assert_eq!(result, Ok("Done!"));
+    }
+
+    #[test]
+    fn elapses() {
+        let result: Result<(), trpl::Elapsed> = trpl::block_on(async {
+            let slow = trpl::sleep(Duration::from_secs(5));
+            trpl::timeout(Duration::from_millis(1), slow).await
+        });
+        assert!(result.is_err());
+    }
+}
+
+#[cfg(feature = "test-util")]
+mod paused_clock_works {
+    use std::time::Instant;
+
+    use trpl::StreamExt;
+
+    use super::*;
+
+    #[test]
+    fn long_sleeps_finish_instantly() {
+        let start = Instant::now();
+        let result = trpl::block_on_paused(async {
+            let slow = async {
+                trpl::sleep(Duration::from_secs(60)).await;
+                "Slow"
+            };
+            let timed_out =
+                trpl::timeout(Duration::from_secs(30), slow).await.is_err();
+            let slow_again = async {
+                trpl::sleep(Duration::from_secs(60)).await;
+                "Slow"
+            };
+            let finished =
+                trpl::timeout(Duration::from_secs(90), slow_again).await;
+            (timed_out, finished)
+        });
+        assert_eq!(result, (true, Ok("Slow")));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn intervals_tick_without_waiting() {
+        let start = Instant::now();
+        let ticks = trpl::block_on_paused(async {
+            trpl::IntervalStream::new(trpl::interval(Duration::from_secs(10)))
+                .take(6)
+                .collect::<Vec<_>>()
+                .await
+                .len()
+        });
+        assert_eq!(ticks, 6);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn advancing_by_hand() {
+        trpl::block_on_paused(async {
+            let (tx, mut rx) = trpl::channel();
+            trpl::spawn_task(async move {
+                trpl::sleep(Duration::from_secs(5)).await;
+                tx.send("Woke up").unwrap();
+            });
+            // Let the task start sleeping.
+            trpl::yield_now().await;
+
+            trpl::advance(Duration::from_secs(4)).await;
+            assert!(rx.try_recv().is_err());
+
+            trpl::advance(Duration::from_secs(1)).await;
+            assert_eq!(rx.recv().await, Some("Woke up"));
+        });
+    }
+}